@@ -3,6 +3,7 @@ use std::alloc::GlobalAlloc;
 
 const SMALL_ALLOC_SIZE: usize = 0x1FFE001;
 const BIG_ALLOC_SIZE: usize = 0x3000000;
+#[allow(dead_code)]
 struct TestType([f64; 4]);
 impl TestType {
     fn new(src: f64) -> Self {
@@ -21,6 +22,17 @@ fn system_alloc(bench: &mut Criterion) {
         })
     });
 }
+fn page_allocator_alloc(bench: &mut Criterion) {
+    let allocator = memory_pages::PageAllocator::new();
+    let layout = std::alloc::Layout::from_size_align(BIG_ALLOC_SIZE, 1).unwrap();
+    bench.bench_function("page_allocator_alloc", |b| {
+        b.iter(|| {
+            let ptr = unsafe { allocator.alloc(layout) };
+            assert_ne!(ptr as usize, 0);
+            unsafe { allocator.dealloc(black_box(ptr), layout) }
+        })
+    });
+}
 fn page_alloc(bench: &mut Criterion) {
     use memory_pages::*;
     bench.bench_function("page_alloc", |b| {
@@ -68,7 +80,6 @@ fn push_10m_f64_pv(bench: &mut Criterion) {
     black_box(vec);
 }
 fn push_10m_f64_v(bench: &mut Criterion) {
-    use memory_pages::*;
     let mut vec = Vec::with_capacity(1_000_000);
     bench.bench_function("push_10m_f64_v", |b| {
         b.iter(|| {
@@ -98,7 +109,6 @@ fn push_test_type_pv(bench: &mut Criterion) {
     black_box(&mut vec);
 }
 fn push_test_type_v(bench: &mut Criterion) {
-    use memory_pages::*;
     let mut vec = Vec::with_capacity(1_000_000);
     bench.bench_function("push_test_type_v", |b| {
         b.iter(|| {
@@ -113,7 +123,7 @@ fn push_test_type_v(bench: &mut Criterion) {
 fn random_rw_pv(bench: &mut Criterion) {
     use memory_pages::*;
     fn prep() -> PagedVec<usize> {
-        let mut vec = PagedVec::new(0x1000_000);
+        let mut vec = PagedVec::new(0x0100_0000);
         for i in 0..vec.capacity() {
             let val = i;
             vec.push(val);
@@ -124,7 +134,7 @@ fn random_rw_pv(bench: &mut Criterion) {
     let mut vec = prep();
     let mut idx = 0;
     bench.bench_function("random_rw_pv", |b| {
-        let mut prev = idx;
+        let prev = idx;
         b.iter(|| {
             vec[idx] = vec[prev];
             idx = (idx + 1).min(vec.len() - 1);
@@ -133,7 +143,7 @@ fn random_rw_pv(bench: &mut Criterion) {
 }
 fn random_rw_v(bench: &mut Criterion) {
     fn prep() -> Vec<usize> {
-        let mut vec = Vec::with_capacity(0x1000_000);
+        let mut vec = Vec::with_capacity(0x0100_0000);
         for i in 0..vec.capacity() {
             let val = i;
             vec.push(val);
@@ -143,7 +153,7 @@ fn random_rw_v(bench: &mut Criterion) {
     let mut vec = prep();
     let mut idx = 0;
     bench.bench_function("random_rw_v", |b| {
-        let mut prev = idx;
+        let prev = idx;
         b.iter(|| {
             vec[idx] = vec[prev];
             idx = (idx + 1).min(vec.len() - 1);
@@ -158,6 +168,7 @@ criterion_group!(
     push_test_type_v,
     system_alloc,
     page_alloc,
+    page_allocator_alloc,
     small_system_alloc,
     small_page_alloc,
     push_10m_f64_pv,
@@ -0,0 +1,134 @@
+use crate::*;
+/// [`DualMappedPages`] maps the same memory twice: once as writable but not executable, and once as executable but not
+/// writable. Code can be emitted or patched through the writable view, while it is being executed through the executable
+/// view, without ever flipping permissions of either mapping. Since neither of the mappings is writable and executable at the
+/// same time, W^X policy holds for each of them.
+/// # Beware
+/// Changes made through the writable view are immediately visible in the executable view. Patching code which may be
+/// executed by another thread at the same time must be done with care, e.g. by writing new code to an unused part of the
+/// pages, and only then redirecting jumps to it.
+/// # Examples
+/// A function that just returns, and does nothing. This example is architecture specific.
+/// ```no_run
+/// # use memory_pages::*;
+/// let mut code = DualMappedPages::new(0x1000);
+/// // X86_64 assembly instruction `RET`
+/// code.code_mut()[0] = 0xC3;
+/// let nop:FnRef<unsafe extern "C" fn()> = unsafe{code.get_fn(0)};
+/// unsafe{nop.call(())};
+/// ```
+pub struct DualMappedPages {
+    write: Pages<AllowRead, AllowWrite, DenyExec>,
+    exec: Pages<AllowRead, DenyWrite, AllowExec>,
+}
+impl DualMappedPages {
+    /// Allocates new [`DualMappedPages`] of size at least `length`, rounded up to next page boundary if necessary.
+    /// # Panics
+    /// Panics when a 0-sized allocation is attempted, or if kernel can't/refuses to allocate requested Pages.
+    #[must_use]
+    pub fn new(length: usize) -> Self {
+        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+        Self::try_new(length).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if creating or mapping the shared memory object fails, including
+    /// 0-sized allocations.
+    pub fn try_new(length: usize) -> Result<Self, PagesError> {
+        let write: Pages<AllowRead, AllowWrite, DenyExec> = Pages::try_new_shared(length)?;
+        // Borrowck keeps writes through `code_mut` apart from reads through `exec_view`. Only the unsafe `into_views` lets
+        // both views be used at once, and the object is never handed out otherwise.
+        let exec = unsafe {
+            let fd = write.shared_fd().expect(
+                "Pages created with `try_new_shared` must be backed by a shared memory object",
+            );
+            Pages::map_shared_fd(fd, 0, write.len())
+        }?;
+        Ok(Self { write, exec })
+    }
+    /// Returns the length of each of the views.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.write.len()
+    }
+    /// Always returns `false`, because 0-sized allocations are not allowed. Provided for consistency with [`Self::len`].
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.write.is_empty()
+    }
+    /// Returns the code through the writable view, allowing it to be emitted or patched.
+    pub fn code_mut(&mut self) -> &mut [u8] {
+        &mut self.write
+    }
+    /// Returns the executable view.
+    #[must_use]
+    pub fn exec_view(&self) -> &Pages<AllowRead, DenyWrite, AllowExec> {
+        &self.exec
+    }
+    /// Gets a pointer to function at offset in the executable view. Function must be an `extern "C" fn`.
+    /// # Safety
+    /// Same as [`Pages::get_fn`].
+    /// # Panics
+    /// Will panic if offset larger than length.
+    #[must_use]
+    pub unsafe fn get_fn<F>(&self, offset: usize) -> FnRef<'_, F>
+    where
+        F: ExternFnPtr + Copy + Pointer + Sized,
+    {
+        self.exec.get_fn(offset)
+    }
+    /// Splits `self` into the writable and the executable view, allowing them to be used independently, e.g. by different
+    /// threads. Memory stays mapped as long as any of the views is alive.
+    /// # Safety
+    /// Both views map the same memory, so the borrow checker can't prevent it from being read through the executable view
+    /// while it is changed through the writable one. The caller must ensure that no reference obtained from the executable
+    /// view(e.g. using [`std::ops::Deref`]) is alive while the memory behind it is written to, in this or any other thread.
+    /// Executing code while it is patched is not covered by this, see the *Beware* section of [`DualMappedPages`].
+    #[must_use]
+    pub unsafe fn into_views(
+        self,
+    ) -> (
+        Pages<AllowRead, AllowWrite, DenyExec>,
+        Pages<AllowRead, DenyWrite, AllowExec>,
+    ) {
+        (self.write, self.exec)
+    }
+}
+impl std::fmt::Debug for DualMappedPages {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("DualMappedPages")
+            .field("write", &self.write)
+            .field("exec", &self.exec)
+            .finish()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_dual_mapping() {
+        let mut code = DualMappedPages::new(1);
+        assert_eq!(code.len(), page_size());
+        code.code_mut()[7] = 0x90;
+        assert_eq!(code.exec_view()[7], 0x90);
+        let (mut write, exec) = unsafe { code.into_views() };
+        write[8] = 0x91;
+        assert_eq!(exec[8], 0x91);
+        drop(write);
+        assert_eq!(exec[7], 0x90);
+    }
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_dual_mapping_exec() {
+        let mut code = DualMappedPages::new(0x1000);
+        // Add 2 u64s
+        code.code_mut()[..5].copy_from_slice(&[0x48, 0x8d, 0x04, 0x37, 0xC3]);
+        let add: FnRef<unsafe extern "C" fn(u64, u64) -> u64> = unsafe { code.get_fn(0) };
+        unsafe { assert_eq!(add.call((3, 4)), 7) };
+        // Patch the code through the writable view: `lea rax, [rdi+rsi]` becomes `lea rax, [rdi+rdi]`.
+        let (mut write, exec) = unsafe { code.into_views() };
+        write[3] = 0x3f;
+        let double: FnRef<unsafe extern "C" fn(u64, u64) -> u64> = unsafe { exec.get_fn(0) };
+        unsafe { assert_eq!(double.call((3, 4)), 6) };
+    }
+}
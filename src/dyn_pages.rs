@@ -0,0 +1,263 @@
+use crate::*;
+use std::mem::ManuallyDrop;
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+/// A set of permissions of pages, checked at runtime. Used by [`DynPages`], whose permissions are not known at compile time.
+/// Permissions are combined using `|`.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// let protection = Protection::READ | Protection::WRITE;
+/// assert!(protection.contains(Protection::READ));
+/// assert_eq!(protection.to_string(),"RW-");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Protection(u8);
+impl Protection {
+    /// No access is allowed.
+    pub const NONE: Self = Self(0x0);
+    /// Pages can be read from.
+    pub const READ: Self = Self(0x1);
+    /// Pages can be written into.
+    pub const WRITE: Self = Self(0x2);
+    /// Native instructions inside pages can be executed. See [`AllowExec`] before using it.
+    #[cfg(any(feature = "allow_exec", doc, test))]
+    pub const EXEC: Self = Self(0x4);
+    /// Returns the raw bits of this [`Protection`]: read is `0x1`, write `0x2` and execute `0x4`.
+    #[must_use]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+    /// Returns `true` if all permissions in `other` are also in `self`.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// Returns the permissions of [`Pages<R, W, E>`].
+    #[must_use]
+    pub fn of<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker>() -> Self {
+        Self(Pages::<R, W, E>::protection_index() as u8)
+    }
+    fn index(self) -> usize {
+        usize::from(self.0)
+    }
+}
+impl BitOr for Protection {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+impl BitOrAssign for Protection {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+impl BitAnd for Protection {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+impl std::fmt::Display for Protection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(stats::protection_name(self.index()))
+    }
+}
+impl std::fmt::Debug for Protection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "Protection({self})")
+    }
+}
+/// [`DynPages`] works like [`Pages`], but its permissions are a runtime [`Protection`], instead of being a part of its type.
+/// Access to its contents is checked against the current permissions at runtime. It can be converted from any [`Pages`], and
+/// back into [`Pages`] whose permission markers match its current [`Protection`].
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// // E.g. read from plugin metadata.
+/// let writable = true;
+/// let mut protection = Protection::READ;
+/// if writable{
+///     protection |= Protection::WRITE;
+/// }
+/// let mut memory = DynPages::new(0x1000, protection);
+/// memory.as_mut_slice().unwrap()[0] = 1;
+/// memory.set_protection(Protection::READ).unwrap();
+/// assert!(memory.as_mut_slice().is_none());
+/// let memory:Pages<AllowRead,DenyWrite,DenyExec> = memory.try_into().unwrap();
+/// assert_eq!(memory[0],1);
+/// ```
+pub struct DynPages {
+    /// Markers of those pages are ignored, its actual permissions are described by `protection`.
+    pages: Pages<DenyRead, DenyWrite, DenyExec>,
+    protection: Protection,
+}
+impl DynPages {
+    /// Allocates new [`DynPages`] of size at least `length`, rounded up to next page boundary if necessary, with permissions
+    /// set to `protection`.
+    /// # Panics
+    /// Panics when a 0-sized allocation is attempted, if kernel can't/refuses to allocate requested pages, or if
+    /// `protection` can't be set.
+    #[must_use]
+    pub fn new(length: usize, protection: Protection) -> Self {
+        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+        Self::try_new(length, protection).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if the allocation fails, including 0-sized allocations, or with
+    /// [`PagesOperation::Protect`] if `protection` can't be set.
+    pub fn try_new(length: usize, protection: Protection) -> Result<Self, PagesError> {
+        let mut pages: Self = Pages::<DenyRead, DenyWrite, DenyExec>::try_new(length)?.into();
+        pages.set_protection(protection)?;
+        Ok(pages)
+    }
+    /// Returns the current permissions of this [`DynPages`].
+    #[must_use]
+    pub fn protection(&self) -> Protection {
+        self.protection
+    }
+    /// Changes the permissions of this [`DynPages`] to `protection`. On failure, permissions are left unchanged.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Protect`] if the kernel refuses to change the permissions, or if
+    /// `protection` allows both writing and execution while the `deny_xw` feature is enabled.
+    pub fn set_protection(&mut self, protection: Protection) -> Result<(), PagesError> {
+        const WRITE_EXEC: Protection = Protection(0x6);
+        if cfg!(feature = "deny_xw") && protection.contains(WRITE_EXEC) {
+            return Err(PagesError::invalid_argument(PagesOperation::Protect));
+        }
+        if protection == self.protection {
+            return Ok(());
+        }
+        self.pages.protect(protection.index())?;
+        self.pages
+            .record_protection(self.protection.index(), protection.index());
+        self.protection = protection;
+        Ok(())
+    }
+    /// Returns the length of this [`DynPages`].
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pages.len
+    }
+    /// Always returns `false`, because 0-sized allocations are not allowed. Provided for consistency with [`Self::len`].
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pages.len == 0
+    }
+    /// Returns the contents of this [`DynPages`], or [`None`] if they can't be read.
+    #[must_use]
+    pub fn as_slice(&self) -> Option<&[u8]> {
+        self.protection
+            .contains(Protection::READ)
+            .then(|| unsafe { std::slice::from_raw_parts(self.pages.ptr, self.pages.len) })
+    }
+    /// Returns the contents of this [`DynPages`], or [`None`] if they can't be both read and written.
+    #[must_use]
+    pub fn as_mut_slice(&mut self) -> Option<&mut [u8]> {
+        self.protection
+            .contains(Protection::READ | Protection::WRITE)
+            .then(|| unsafe { std::slice::from_raw_parts_mut(self.pages.ptr, self.pages.len) })
+    }
+    /// Gives the underlying [`Pages`] back, moving them to `NONE` in statistics to match their markers.
+    fn into_pages(self) -> Pages<DenyRead, DenyWrite, DenyExec> {
+        let this = ManuallyDrop::new(self);
+        this.pages
+            .record_protection(this.protection.index(), Protection::NONE.index());
+        // `this` is never dropped, so `pages` is moved out of it exactly once.
+        unsafe { std::ptr::read(&this.pages) }
+    }
+}
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> From<Pages<R, W, E>>
+    for DynPages
+{
+    fn from(pages: Pages<R, W, E>) -> Self {
+        let protection = Protection::of::<R, W, E>();
+        let pages = pages.cast_prot::<DenyRead, DenyWrite, DenyExec>();
+        pages.record_protection(Protection::NONE.index(), protection.index());
+        Self { pages, protection }
+    }
+}
+/// Converts [`DynPages`] into [`Pages`], if its current [`Protection`] matches the permission markers exactly. Otherwise,
+/// gives the [`DynPages`] back unchanged.
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> TryFrom<DynPages>
+    for Pages<R, W, E>
+{
+    type Error = DynPages;
+    fn try_from(pages: DynPages) -> Result<Self, DynPages> {
+        if pages.protection != Protection::of::<R, W, E>() {
+            return Err(pages);
+        }
+        Ok(pages.into_pages().cast_prot())
+    }
+}
+impl Drop for DynPages {
+    fn drop(&mut self) {
+        self.pages
+            .record_protection(self.protection.index(), Protection::NONE.index());
+    }
+}
+impl std::fmt::Debug for DynPages {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("DynPages")
+            .field("ptr", &self.pages.ptr)
+            .field("len", &self.pages.len)
+            .field("protection", &self.protection)
+            .finish()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_dyn_pages() {
+        let mut pages = DynPages::new(1, Protection::NONE);
+        assert_eq!(pages.len(), page_size());
+        assert!(pages.as_slice().is_none());
+        pages
+            .set_protection(Protection::READ | Protection::WRITE)
+            .unwrap();
+        pages.as_mut_slice().unwrap()[3] = 4;
+        pages.set_protection(Protection::READ).unwrap();
+        assert!(pages.as_mut_slice().is_none());
+        assert_eq!(pages.as_slice().unwrap()[3], 4);
+        // Markers must match the protection exactly.
+        let pages = Pages::<AllowRead, AllowWrite, DenyExec>::try_from(pages).unwrap_err();
+        let pages: Pages<AllowRead, DenyWrite, DenyExec> = pages.try_into().unwrap();
+        assert_eq!(pages[3], 4);
+        let pages = DynPages::from(pages.allow_write());
+        assert_eq!(pages.protection(), Protection::READ | Protection::WRITE);
+        assert!(format!("{pages:?}").contains("Protection(RW-)"));
+    }
+    #[test]
+    fn test_dyn_pages_exec() {
+        let mut pages = DynPages::new(1, Protection::READ | Protection::EXEC);
+        assert_eq!(pages.as_slice().unwrap()[0], 0);
+        let res = pages.set_protection(Protection::READ | Protection::WRITE | Protection::EXEC);
+        if cfg!(feature = "deny_xw") {
+            let err = res.unwrap_err();
+            assert_eq!(err.operation(), PagesOperation::Protect);
+            assert_eq!(pages.protection(), Protection::READ | Protection::EXEC);
+        }
+        let _: Pages<AllowRead, DenyWrite, AllowExec> =
+            DynPages::new(1, Protection::READ | Protection::EXEC)
+                .try_into()
+                .unwrap();
+    }
+    #[test]
+    fn test_protection() {
+        assert_eq!(
+            Protection::of::<AllowRead, AllowWrite, DenyExec>(),
+            Protection::READ | Protection::WRITE
+        );
+        assert_eq!(
+            Protection::of::<DenyRead, DenyWrite, DenyExec>(),
+            Protection::NONE
+        );
+        assert_eq!(
+            (Protection::READ | Protection::WRITE) & Protection::WRITE,
+            Protection::WRITE
+        );
+        assert_eq!(Protection::default().bits(), 0);
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::{Display, Formatter};
+/// Describes which operation on memory pages failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PagesOperation {
+    /// Acquiring new pages from the kernel(`mmap`/`VirtualAlloc`).
+    Map,
+    /// Changing permissions of pages(`mprotect`/`VirtualProtect`).
+    Protect,
+    /// Changing the size of pages(`mremap`).
+    Remap,
+    /// Releasing pages back to the kernel(`munmap`/`VirtualFree`).
+    Unmap,
+    /// Making reserved pages usable(`mprotect`/`VirtualAlloc` with `MEM_COMMIT`).
+    Commit,
+    /// Returning committed pages to the reserved state(`mmap`/`VirtualFree` with `MEM_DECOMMIT`).
+    Uncommit,
+    /// Writing modified pages back to the file they map(`msync`/`FlushViewOfFile`).
+    Flush,
+    /// Splitting one mapping into two independently owned ones.
+    Split,
+    /// Pinning pages in physical memory(`mlock`/`VirtualLock`).
+    Lock,
+    /// Allowing pinned pages to be swapped out again(`munlock`/`VirtualUnlock`).
+    Unlock,
+    /// Changing how the kernel treats pages(`madvise`/`mbind`/`set_mempolicy`).
+    Advise,
+    /// Querying the state of pages(`mincore`/`move_pages`).
+    Query,
+}
+impl Display for PagesOperation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let name = match self {
+            Self::Map => "mapping pages",
+            Self::Protect => "changing page protection",
+            Self::Remap => "remapping pages",
+            Self::Unmap => "unmapping pages",
+            Self::Commit => "committing pages",
+            Self::Uncommit => "uncommitting pages",
+            Self::Flush => "flushing pages",
+            Self::Split => "splitting pages",
+            Self::Lock => "locking pages",
+            Self::Unlock => "unlocking pages",
+            Self::Advise => "advising on pages",
+            Self::Query => "querying pages",
+        };
+        f.write_str(name)
+    }
+}
+/// An error returned when the kernel refuses to perform an operation on memory pages. It carries the operation which failed and
+/// the raw OS error code(`errno` on unix-like systems, `GetLastError` on windows) describing why it failed.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// // 0-sized mappings are rejected by the kernel.
+/// let err = Pages::<AllowRead, AllowWrite, DenyExec>::try_new(0).unwrap_err();
+/// assert_eq!(err.operation(), PagesOperation::Map);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PagesError {
+    operation: PagesOperation,
+    code: i32,
+}
+impl PagesError {
+    pub(crate) fn new(operation: PagesOperation, code: i32) -> Self {
+        Self { operation, code }
+    }
+    /// Creates a [`PagesError`] for `operation` from the last OS error of the calling thread.
+    pub(crate) fn last_os_error(operation: PagesOperation) -> Self {
+        #[cfg(target_family = "unix")]
+        let code = crate::erno();
+        #[cfg(target_family = "windows")]
+        let code = unsafe { winapi::um::errhandlingapi::GetLastError() } as i32;
+        Self::new(operation, code)
+    }
+    /// Creates a [`PagesError`] for `operation` rejected because of invalid arguments.
+    pub(crate) fn invalid_argument(operation: PagesOperation) -> Self {
+        #[cfg(target_family = "unix")]
+        const EINVAL: i32 = 22;
+        #[cfg(target_family = "windows")]
+        const EINVAL: i32 = winapi::shared::winerror::ERROR_INVALID_PARAMETER as i32;
+        Self::new(operation, EINVAL)
+    }
+    /// Returns the operation that failed.
+    #[must_use]
+    pub fn operation(&self) -> PagesOperation {
+        self.operation
+    }
+    /// Returns the raw OS error code(`errno` or `GetLastError`) reported when the operation failed.
+    #[must_use]
+    pub fn raw_os_error(&self) -> i32 {
+        self.code
+    }
+    /// Returns `true` if locking pages failed because the process would exceed the amount of memory it is allowed to lock
+    /// (`RLIMIT_MEMLOCK` on unix-like systems, the minimum working set size on windows).
+    #[must_use]
+    pub fn is_lock_limit_exceeded(&self) -> bool {
+        #[cfg(target_family = "unix")]
+        const LIMIT_ERRORS: [i32; 2] = [1 /* EPERM */, 12 /* ENOMEM */];
+        #[cfg(target_family = "windows")]
+        const LIMIT_ERRORS: [i32; 1] = [winapi::shared::winerror::ERROR_WORKING_SET_QUOTA as i32];
+        self.operation == PagesOperation::Lock && LIMIT_ERRORS.contains(&self.code)
+    }
+}
+impl Display for PagesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let os_err = std::io::Error::from_raw_os_error(self.code);
+        write!(f, "{} failed: {os_err}", self.operation)?;
+        if self.is_lock_limit_exceeded() {
+            f.write_str(" (the limit of locked memory, e.g. `RLIMIT_MEMLOCK`, would be exceeded)")?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for PagesError {}
+impl From<PagesError> for std::io::Error {
+    fn from(err: PagesError) -> Self {
+        std::io::Error::from_raw_os_error(err.code)
+    }
+}
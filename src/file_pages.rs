@@ -0,0 +1,285 @@
+use crate::*;
+use std::fs::File;
+/// Describes how changes to [`Pages`] mapping a file are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapMode {
+    /// Changes are written back to the file, and are visible to all other mappings of it. Use [`Pages::flush`] to ensure they
+    /// reached the file.
+    Shared,
+    /// Changes are private to this mapping(copy-on-write), and are never written back to the file.
+    Private,
+}
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Maps `length` bytes of `file`, starting at `offset`, into memory. `length` is rounded up to next page boundary if
+    /// necessary, with bytes past the end of the file being read as 0. Permissions of the mapping must be compatible with
+    /// the way `file` was opened: a [`File`] opened read-only may be mapped only as `Pages<AllowRead, DenyWrite, DenyExec>`,
+    /// or with [`MapMode::Private`].
+    /// # Safety
+    /// The file is not owned by the returned [`Pages`], so the caller must ensure that, for as long as the mapping lives:
+    /// 1. The file is not truncated(by this or any other process), since accessing mapped memory past the end of the file
+    ///    raises SIGBUS.
+    /// 2. With [`MapMode::Shared`], the mapped range is not changed by anything else(other mappings of the file, writes to
+    ///    it, other processes), since that would change memory behind the references [`Pages`] gives out.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if `offset` is not a multiple of [`allocation_granularity`], if
+    /// the mapped range is empty or does not lie within the file, or if the kernel can't/refuses to map the file.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// # use std::io::Write;
+    /// # let path = std::env::temp_dir().join("memory_pages_map_file_doc");
+    /// # std::fs::File::create(&path).unwrap().write_all(b"Hello, pages!").unwrap();
+    /// let file = std::fs::File::open(&path).unwrap();
+    /// // The file is not changed while it is mapped.
+    /// let memory:Pages<AllowRead,DenyWrite,DenyExec> =
+    ///     unsafe { Pages::map_file(&file, 0, 13, MapMode::Shared) }.unwrap();
+    /// assert_eq!(memory.get(..13).unwrap(), b"Hello, pages!");
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub unsafe fn map_file(
+        file: &File,
+        offset: u64,
+        length: usize,
+        mode: MapMode,
+    ) -> Result<Self, PagesError> {
+        let file_len = file.metadata().map_err(|err| {
+            err.raw_os_error().map_or_else(
+                || PagesError::invalid_argument(PagesOperation::Map),
+                |code| PagesError::new(PagesOperation::Map, code),
+            )
+        })?;
+        let in_file = offset
+            .checked_add(length as u64)
+            .is_some_and(|end| end <= file_len.len());
+        if length == 0 || !in_file || !offset.is_multiple_of(allocation_granularity() as u64) {
+            return Err(PagesError::invalid_argument(PagesOperation::Map));
+        }
+        let mut pages = Self::map_file_native(file, offset, next_page_boundary(length), mode)?;
+        pages.file = Some(mode);
+        Ok(pages)
+    }
+    #[cfg(target_family = "unix")]
+    fn map_file_native(
+        file: &File,
+        offset: u64,
+        len: usize,
+        mode: MapMode,
+    ) -> Result<Self, PagesError> {
+        use std::os::unix::io::AsRawFd;
+        const MAP_SHARED: c_int = 0x1;
+        let flags = match mode {
+            MapMode::Shared => MAP_SHARED,
+            MapMode::Private => MAP_PRIVATE,
+        };
+        let offset = usize::try_from(offset)
+            .map_err(|_| PagesError::invalid_argument(PagesOperation::Map))?;
+        let ptr = unsafe {
+            mmap(
+                std::ptr::null_mut(),
+                len,
+                Self::bitmask(),
+                flags,
+                file.as_raw_fd(),
+                offset,
+            )
+        };
+        if ptr as usize == usize::MAX {
+            return Err(PagesError::last_os_error(PagesOperation::Map));
+        }
+        Ok(Self::from_raw_parts(ptr.cast::<u8>(), len))
+    }
+    #[cfg(target_family = "windows")]
+    fn map_file_native(
+        file: &File,
+        offset: u64,
+        len: usize,
+        mode: MapMode,
+    ) -> Result<Self, PagesError> {
+        use std::os::windows::io::AsRawHandle;
+        use winapi::um::handleapi::CloseHandle;
+        use winapi::um::winnt::{PAGE_EXECUTE_WRITECOPY, PAGE_WRITECOPY};
+        let (map_prot, access) = match (mode, W::allow_write(), E::allow_exec()) {
+            (_, false, false) => (PAGE_READONLY, FILE_MAP_READ),
+            (_, false, true) => (PAGE_EXECUTE_READ, FILE_MAP_READ | FILE_MAP_EXECUTE),
+            (MapMode::Shared, true, false) => (PAGE_READWRITE, FILE_MAP_WRITE),
+            (MapMode::Shared, true, true) => {
+                (PAGE_EXECUTE_READWRITE, FILE_MAP_WRITE | FILE_MAP_EXECUTE)
+            }
+            (MapMode::Private, true, false) => (PAGE_WRITECOPY, FILE_MAP_COPY),
+            (MapMode::Private, true, true) => {
+                (PAGE_EXECUTE_WRITECOPY, FILE_MAP_COPY | FILE_MAP_EXECUTE)
+            }
+        };
+        let mapping = unsafe {
+            CreateFileMappingW(
+                file.as_raw_handle().cast(),
+                std::ptr::null_mut(),
+                map_prot,
+                0,
+                0,
+                std::ptr::null(),
+            )
+        };
+        if mapping.is_null() {
+            return Err(PagesError::last_os_error(PagesOperation::Map));
+        }
+        let ptr =
+            unsafe { MapViewOfFile(mapping, access, (offset >> 32) as u32, offset as u32, len) };
+        let err = PagesError::last_os_error(PagesOperation::Map);
+        // The view keeps the mapping object alive on its own.
+        unsafe { CloseHandle(mapping) };
+        if ptr.is_null() {
+            return Err(err);
+        }
+        let mut pages = Self::from_raw_parts(ptr.cast::<u8>(), len);
+        pages.file = Some(mode);
+        // Views can't be created inaccessible, so read permission must be removed afterwards.
+        if !R::allow_read() {
+            pages.set_prot()?;
+        }
+        Ok(pages)
+    }
+    /// Returns the [`MapMode`] of the file mapped by this [`Pages`], or [`None`] if it does not map a file.
+    #[must_use]
+    pub fn map_mode(&self) -> Option<MapMode> {
+        self.file
+    }
+    /// Writes all changes made to this [`Pages`] back to the file it maps, and waits for the write to finish. Does nothing
+    /// if this [`Pages`] does not map a file with [`MapMode::Shared`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Flush`] if writing the changes back fails.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// # let path = std::env::temp_dir().join("memory_pages_flush_doc");
+    /// # std::fs::write(&path, [0; 16]).unwrap();
+    /// let file = std::fs::OpenOptions::new().read(true).write(true).open(&path).unwrap();
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> =
+    ///     unsafe { Pages::map_file(&file, 0, 16, MapMode::Shared) }.unwrap();
+    /// memory[0] = 42;
+    /// memory.flush().unwrap();
+    /// assert_eq!(std::fs::read(&path).unwrap()[0], 42);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn flush(&self) -> Result<(), PagesError> {
+        self.flush_native(false)
+    }
+    /// Starts writing all changes made to this [`Pages`] back to the file it maps, without waiting for the write to finish.
+    /// Does nothing if this [`Pages`] does not map a file with [`MapMode::Shared`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Flush`] if scheduling the write fails.
+    pub fn flush_async(&self) -> Result<(), PagesError> {
+        self.flush_native(true)
+    }
+    #[cfg(target_family = "unix")]
+    fn flush_native(&self, is_async: bool) -> Result<(), PagesError> {
+        const MS_ASYNC: c_int = 0x1;
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        const MS_SYNC: c_int = 0x10;
+        #[cfg(target_os = "freebsd")]
+        const MS_SYNC: c_int = 0x0;
+        #[cfg(not(any(target_os = "macos", target_os = "ios", target_os = "freebsd")))]
+        const MS_SYNC: c_int = 0x4;
+        if self.file != Some(MapMode::Shared) {
+            return Ok(());
+        }
+        let flags = if is_async { MS_ASYNC } else { MS_SYNC };
+        if unsafe { msync(self.ptr.cast::<c_void>(), self.len, flags) } == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Flush));
+        }
+        Ok(())
+    }
+    #[cfg(target_family = "windows")]
+    fn flush_native(&self, _is_async: bool) -> Result<(), PagesError> {
+        if self.file != Some(MapMode::Shared) {
+            return Ok(());
+        }
+        if unsafe { FlushViewOfFile(self.ptr.cast::<winapi::ctypes::c_void>(), self.len) } == 0 {
+            return Err(PagesError::last_os_error(PagesOperation::Flush));
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+    fn test_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("memory_pages_{name}_{}", std::process::id()));
+        File::create(&path).unwrap().write_all(contents).unwrap();
+        path
+    }
+    #[test]
+    fn test_map_read_only() {
+        let path = test_file("read_only", b"abcdef");
+        let file = File::open(&path).unwrap();
+        let pages: Pages<AllowRead, DenyWrite, DenyExec> =
+            unsafe { Pages::map_file(&file, 0, 6, MapMode::Shared) }.unwrap();
+        assert_eq!(pages.get(..6).unwrap(), b"abcdef");
+        assert_eq!(pages.map_mode(), Some(MapMode::Shared));
+        // A file opened read-only can't be mapped as writable and shared.
+        assert!(unsafe {
+            Pages::<AllowRead, AllowWrite, DenyExec>::map_file(&file, 0, 6, MapMode::Shared)
+        }
+        .is_err());
+        // Making pages writable fails, but gives them back unchanged.
+        let (pages, err) = pages.try_allow_write().unwrap_err();
+        assert_eq!(err.operation(), PagesOperation::Protect);
+        assert_eq!(pages.get(..6).unwrap(), b"abcdef");
+        std::fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn test_map_shared_and_private() {
+        let path = test_file("shared", &[1; 64]);
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .unwrap();
+        let mut private: Pages<AllowRead, AllowWrite, DenyExec> =
+            unsafe { Pages::map_file(&file, 0, 64, MapMode::Private) }.unwrap();
+        private[0] = 2;
+        private.flush().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap()[0], 1);
+        let mut shared: Pages<AllowRead, AllowWrite, DenyExec> =
+            unsafe { Pages::map_file(&file, 0, 64, MapMode::Shared) }.unwrap();
+        shared[1] = 3;
+        shared.flush_async().unwrap();
+        shared.flush().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap()[1], 3);
+        drop(shared);
+        drop(private);
+        std::fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn test_map_invalid_range() {
+        let path = test_file("invalid", &[0; 16]);
+        let file = File::open(&path).unwrap();
+        let map = |offset, len| unsafe {
+            Pages::<AllowRead, DenyWrite, DenyExec>::map_file(&file, offset, len, MapMode::Private)
+        };
+        assert!(map(0, 0).is_err());
+        assert!(map(0, 17).is_err());
+        assert!(map(1, 8).is_err());
+        assert!(map(u64::MAX, 1).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn test_map_resize() {
+        let path = test_file("resize", &[0; 16]);
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .unwrap();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> =
+            unsafe { Pages::map_file(&file, 0, 16, MapMode::Shared) }.unwrap();
+        pages[0] = 1;
+        // Memory past the end of the file can't be accessed, so growing is refused.
+        let err = pages.try_resize(4 * page_size()).unwrap_err();
+        assert_eq!(err.operation(), PagesOperation::Remap);
+        assert_eq!(pages.len(), page_size());
+        assert_eq!(pages[0], 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -42,6 +42,8 @@ pub trait UnsafeCallable<Args> {
     /// Return type of represented function
     type Ret;
     /// Calls the underlying function.
+    /// # Safety
+    /// The underlying function must be valid to call with `args`, which can't be checked at compile time.
     unsafe fn call(&self, args: Args) -> Self::Ret;
 }
 impl<'a, Ret> UnsafeCallable<()> for FnRef<'a, unsafe extern "C" fn() -> Ret> {
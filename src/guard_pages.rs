@@ -0,0 +1,100 @@
+use crate::*;
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Allocates new [`Pages`] of size at least `length`, surrounded by inaccessible guard regions of size at least `guard_size`
+    /// before and after them. Both sizes are rounded up to the next page boundary if necessary. Any access past either end of
+    /// the returned [`Pages`] hits a guard region and causes a segfault, instead of silently reading or corrupting neighbouring
+    /// memory. Guard regions are not a part of the returned [`Pages`]: they are not counted by `len`, and can't be accessed
+    /// in any way, but they are released together with the [`Pages`] they guard.
+    /// # Panics
+    /// Panics when a 0-sized allocation is attempted, or if kernel can't/refuses to allocate requested Pages.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new_guarded(0x1234, page_size());
+    /// // Guards do not count towards the length.
+    /// assert_eq!(memory.len(),2 * page_size());
+    /// assert_eq!(memory.guard_size(),page_size());
+    /// memory[0] = 1;
+    /// ```
+    /// Overruns fault immediately.
+    /// ```no_run
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new_guarded(0x1000, 0x1000);
+    /// let ptr = memory.get_ptr_mut(0);
+    /// // Segfault: writes into the guard region after `memory`.
+    /// unsafe{*ptr.add(memory.len()) = 0};
+    /// ```
+    #[must_use]
+    pub fn new_guarded(length: usize, guard_size: usize) -> Self {
+        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+        Self::try_new_guarded(length, guard_size).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new_guarded`]. If `guard_size` is 0, works exactly like [`Self::try_new`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if the allocation fails, including 0-sized allocations, or with
+    /// [`PagesOperation::Protect`] if permissions of the usable region could not be set.
+    pub fn try_new_guarded(length: usize, guard_size: usize) -> Result<Self, PagesError> {
+        let guard = next_page_boundary(guard_size);
+        if guard == 0 {
+            return Self::try_new(length);
+        }
+        let len = next_page_boundary(length);
+        let total = guard
+            .checked_mul(2)
+            .and_then(|guards| guards.checked_add(len))
+            .filter(|_| len != 0)
+            .ok_or_else(|| PagesError::invalid_argument(PagesOperation::Map))?;
+        let reservation: Pages<DenyRead, DenyWrite, DenyExec> = Pages::try_new(total)?;
+        let (base, _) = reservation.into_raw_parts();
+        let mut pages = Self::from_raw_parts(base.wrapping_add(guard), len);
+        pages.guard = guard;
+        // On failure, dropping `pages` releases the whole reservation, guards included.
+        pages.set_prot()?;
+        Ok(pages)
+    }
+    /// Returns the size of each of the guard regions surrounding this [`Pages`], or 0 if it has no guard regions.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1000);
+    /// assert_eq!(memory.guard_size(),0);
+    /// ```
+    #[must_use]
+    pub fn guard_size(&self) -> usize {
+        self.guard
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_guarded_alloc() {
+        let page_size = page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new_guarded(1, 1);
+        assert_eq!(pages.len(), page_size);
+        assert_eq!(pages.guard_size(), page_size);
+        pages[0] = 1;
+        pages[page_size - 1] = 2;
+        let pages = pages.deny_write();
+        assert_eq!(pages[0], 1);
+        assert_eq!(pages[page_size - 1], 2);
+    }
+    #[test]
+    fn test_guarded_zero() {
+        assert!(Pages::<AllowRead, AllowWrite, DenyExec>::try_new_guarded(0, 0x1000).is_err());
+        let pages = Pages::<AllowRead, AllowWrite, DenyExec>::try_new_guarded(0x1000, 0).unwrap();
+        assert_eq!(pages.guard_size(), 0);
+    }
+    #[test]
+    fn test_guarded_resize() {
+        let page_size = page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> =
+            Pages::new_guarded(page_size, 2 * page_size);
+        pages[page_size - 1] = 9;
+        pages.resize(4 * page_size);
+        assert_eq!(pages.len(), 4 * page_size);
+        assert_eq!(pages.guard_size(), 2 * page_size);
+        assert_eq!(pages[page_size - 1], 9);
+        pages[4 * page_size - 1] = 10;
+    }
+}
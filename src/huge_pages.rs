@@ -0,0 +1,142 @@
+use crate::*;
+/// Size of huge pages backing [`Pages`]. Huge pages reduce the number of TLB entries needed to access large data sets, which
+/// can substantially speed up random access to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HugePageSize {
+    /// Huge pages of size 2 MiB.
+    Size2MiB,
+    /// Huge pages of size 1 GiB.
+    Size1GiB,
+}
+impl HugePageSize {
+    /// Returns the size of a huge page, in bytes.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// assert_eq!(HugePageSize::Size2MiB.bytes(),0x20_0000);
+    /// assert_eq!(HugePageSize::Size1GiB.bytes(),0x4000_0000);
+    /// ```
+    #[must_use]
+    pub const fn bytes(self) -> usize {
+        match self {
+            Self::Size2MiB => 0x20_0000,
+            Self::Size1GiB => 0x4000_0000,
+        }
+    }
+    #[cfg(target_os = "linux")]
+    fn mmap_flags(self) -> c_int {
+        const MAP_HUGETLB: c_int = 0x40000;
+        const MAP_HUGE_SHIFT: c_int = 26;
+        MAP_HUGETLB | ((self.bytes().trailing_zeros() as c_int) << MAP_HUGE_SHIFT)
+    }
+}
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Allocates new [`Pages`] backed by huge pages of size `huge_size`, with length at least `length`, rounded up to next huge
+    /// page boundary if necessary. If the kernel has no huge pages of that size available(or does not support them at all),
+    /// falls back to normal pages. Use [`Self::huge_page_size`] to check which kind of pages was used.
+    /// # Panics
+    /// Panics when a 0-sized allocation is attempted, or if kernel can't/refuses to allocate requested Pages.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new_huge(0x1234, HugePageSize::Size2MiB);
+    /// // Length is always rounded up to the huge page size, even if normal pages had to be used.
+    /// assert_eq!(memory.len(),HugePageSize::Size2MiB.bytes());
+    /// ```
+    #[must_use]
+    pub fn new_huge(length: usize, huge_size: HugePageSize) -> Self {
+        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+        Self::try_new_huge(length, huge_size).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new_huge`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if allocating both huge and normal pages fails, including 0-sized allocations.
+    pub fn try_new_huge(length: usize, huge_size: HugePageSize) -> Result<Self, PagesError> {
+        let len = next_boundary(length, huge_size.bytes());
+        #[cfg(target_os = "linux")]
+        if len != 0 {
+            if let Ok(mut pages) = Self::mmap_anonymous(len, huge_size.mmap_flags()) {
+                pages.huge = Some(huge_size);
+                return Ok(pages);
+            }
+        }
+        Self::try_new(len)
+    }
+    /// Returns the size of huge pages backing this [`Pages`], or [`None`] if it is backed by normal pages.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1000);
+    /// assert_eq!(memory.huge_page_size(),None);
+    /// ```
+    #[must_use]
+    pub fn huge_page_size(&self) -> Option<HugePageSize> {
+        self.huge
+    }
+    /// Advises this [`Pages`] to be backed by transparent huge pages, if the kernel supports them.
+    /// # Beware
+    /// Usage hints are part of fine-grain memory access adjustments. It is *NOT* always beneficial to use, in
+    /// contrary, it very often slows allocations down. Before using those hints, test each usage.
+    pub fn advise_hugepage(&mut self) {
+        #[cfg(target_os = "linux")]
+        unsafe {
+            const MADV_HUGEPAGE: c_int = 14;
+            madvise(self.ptr as *mut c_void, self.len, MADV_HUGEPAGE);
+        }
+    }
+    /// Advises this [`Pages`] not to be backed by transparent huge pages.
+    /// # Beware
+    /// Usage hints are part of fine-grain memory access adjustments. It is *NOT* always beneficial to use, in
+    /// contrary, it very often slows allocations down. Before using those hints, test each usage.
+    pub fn advise_nohugepage(&mut self) {
+        #[cfg(target_os = "linux")]
+        unsafe {
+            const MADV_NOHUGEPAGE: c_int = 15;
+            madvise(self.ptr as *mut c_void, self.len, MADV_NOHUGEPAGE);
+        }
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_huge_alloc() {
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> =
+            Pages::new_huge(1, HugePageSize::Size2MiB);
+        assert_eq!(pages.len(), HugePageSize::Size2MiB.bytes());
+        pages[0x1F_FFFF] = 7;
+        pages.advise_hugepage();
+        pages.advise_nohugepage();
+        assert_eq!(pages[0x1F_FFFF], 7);
+        let pages = pages.deny_write();
+        assert_eq!(pages[0x1F_FFFF], 7);
+    }
+    #[test]
+    fn test_huge_resize() {
+        let huge = HugePageSize::Size2MiB.bytes();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> =
+            Pages::new_huge(1, HugePageSize::Size2MiB);
+        pages[0] = 1;
+        match pages.huge_page_size() {
+            // No huge pages available, so normal pages were used, which can always be grown.
+            None => {
+                assert_eq!(pages.granularity(), page_size());
+                pages.resize(huge + 1);
+                assert_eq!(pages.len(), huge + page_size());
+            }
+            Some(size) => {
+                assert_eq!(size, HugePageSize::Size2MiB);
+                assert_eq!(pages.granularity(), huge);
+                match pages.try_resize(huge + 1) {
+                    Ok(()) => assert_eq!(pages.len(), 2 * huge),
+                    // The huge page pool may have no page left, in which case `pages` must be left untouched.
+                    Err(err) => {
+                        assert_eq!(err.operation(), PagesOperation::Remap);
+                        assert_eq!(pages.len(), huge);
+                    }
+                }
+            }
+        }
+        assert_eq!(pages[0], 1);
+    }
+}
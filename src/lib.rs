@@ -3,36 +3,116 @@
 //! `memory_pages` is a small crate providing a cross-platform API to request pages from kernel with certain permission modes
 //! set(read,write,execute). It provides an very safe API to aid in many use cases, mainly:
 //! 1. Speeds up operating on large data sets: [`PagedVec`] provides allocation speed advantages over standard [`Vec`] for large data.
-//! types.
+//!    types.
 //! 2. Page alignment guarantee. Since the API returns memory pages, the first address inside [`Pages`] must be aligned to a page boundary. This means, that with a bit of careful selection of type sizes(powers of 2), a substantial speedup can be occurred(structures can be guaranteed to always reside entirely within 1 page). Those sorts of guarantees are not normally given by allocators.
 //! 3. Simplifies dealing with page permissions and allows for additional levels of safety: Pages with [`DenyWrite`] cannot be
-//! written into without their permissions being changed, which allows for certain kinds of bugs to cause segfaults insted of overwriting data.
+//!    written into without their permissions being changed, which allows for certain kinds of bugs to cause segfaults insted of overwriting data.
 //! 4. Simplifies JITs - while dealing with memory pages is simple compared to difficulty of the task, which is writing a
-//! Just-In-Time compiler, this crate abstracts the platform specific differences away and adds additional measures to prevent
-//! some security issues, allowing you to focus on writing the compiler itself, without worrying about those low-level details.
+//!    Just-In-Time compiler, this crate abstracts the platform specific differences away and adds additional measures to prevent
+//!    some security issues, allowing you to focus on writing the compiler itself, without worrying about those low-level details.
 //! # Features
 //! `allow_exec` - this feature allows access to everything related to executing code inside allocated pages. Off by default.
+//! `track_mappings` - records every live [`Pages`] together with the backtrace of where it was created, allowing leaked
+//! mappings to be found using [`live_mappings`] and [`leak_report`]. Off by default, since capturing backtraces is slow.
 //! `deny_xw` - default feature that prevents allowing both `eXecution` and `Write` permissions on a page. This is an additional security feature that prevents accidental misuse of the API-s locked behind `allow_exec` feature. Does noting without it, but is really usefull when `allow_exec` enabled.
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_doc_code_examples)]
 
+#[cfg(all(any(feature = "allow_exec", doc, test), target_os = "linux"))]
+mod dual_pages;
+mod dyn_pages;
+mod error;
 #[cfg(any(feature = "allow_exec", doc, test))]
 mod extern_fn_ptr;
+mod file_pages;
+mod guard_pages;
+mod huge_pages;
+mod locked_pages;
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+mod numa_pages;
+mod page_allocator;
+mod page_arena;
+mod page_box;
+#[cfg(target_os = "linux")]
+mod page_ring_buffer;
+mod page_slab;
 mod paged_vec;
+mod pod;
+#[cfg(any(feature = "track_mappings", doc, test))]
+mod registry;
+mod reserved_pages;
+#[cfg(target_family = "unix")]
+mod resident_pages;
+mod secret_pages;
+#[cfg(target_family = "unix")]
+mod shared_pages;
+mod split_pages;
+mod stats;
 #[cfg(any(feature = "allow_exec", doc, test))]
 use core::fmt::Pointer;
 #[cfg(any(feature = "allow_exec", doc, test))]
 mod fn_ref;
+#[doc(inline)]
+#[cfg(all(any(feature = "allow_exec", doc, test), target_os = "linux"))]
+pub use dual_pages::*;
+#[doc(inline)]
+pub use dyn_pages::*;
+#[doc(inline)]
+pub use error::*;
 #[cfg(any(feature = "allow_exec", doc, test))]
 use extern_fn_ptr::ExternFnPtr;
 #[doc(inline)]
+pub use file_pages::*;
+#[doc(inline)]
 #[cfg(any(feature = "allow_exec", doc, test))]
 pub use fn_ref::*;
 #[doc(inline)]
+pub use huge_pages::*;
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+#[doc(inline)]
+pub use numa_pages::*;
+#[doc(inline)]
+pub use page_allocator::*;
+#[doc(inline)]
+pub use page_arena::*;
+#[doc(inline)]
+pub use page_box::*;
+#[doc(inline)]
+#[cfg(target_os = "linux")]
+pub use page_ring_buffer::*;
+#[doc(inline)]
+pub use page_slab::*;
+#[doc(inline)]
 pub use paged_vec::*;
+#[doc(inline)]
+pub use pod::*;
+#[doc(inline)]
+#[cfg(any(feature = "track_mappings", doc, test))]
+pub use registry::*;
+#[doc(inline)]
+pub use reserved_pages::*;
+#[doc(inline)]
+pub use secret_pages::*;
+#[doc(inline)]
+pub use stats::*;
 use std::borrow::{Borrow, BorrowMut};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
 #[cfg(target_family = "windows")]
 use winapi::um::memoryapi::*;
 #[cfg(target_family = "windows")]
@@ -40,10 +120,92 @@ use winapi::um::winnt::{
     MEM_COMMIT, MEM_RELEASE, PAGE_EXECUTE, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE,
     PAGE_NOACCESS, PAGE_READONLY, PAGE_READWRITE,
 };
-const fn next_page_boundary(size: usize) -> usize {
-    ((size + PAGE_SIZE - 1) / PAGE_SIZE) * PAGE_SIZE
+fn next_boundary(size: usize, boundary: usize) -> usize {
+    size.div_ceil(boundary) * boundary
+}
+fn prev_boundary(size: usize, boundary: usize) -> usize {
+    (size / boundary) * boundary
+}
+fn next_page_boundary(size: usize) -> usize {
+    next_boundary(size, page_size())
+}
+static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATION_GRANULARITY: AtomicUsize = AtomicUsize::new(0);
+/// Returns the size of a memory page on this system, in bytes. All [`Pages`] start on a boundary of, and have a length which is
+/// a multiple of this size. The size is queried from the kernel once, and then cached.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// let page_size = page_size();
+/// // Page size is always a power of 2.
+/// assert!(page_size.is_power_of_two());
+/// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(1);
+/// assert_eq!(memory.len(),page_size);
+/// assert_eq!(memory.get_ptr(0) as usize % page_size,0);
+/// ```
+#[must_use]
+pub fn page_size() -> usize {
+    match PAGE_SIZE.load(Ordering::Relaxed) {
+        0 => {
+            let page_size = query_page_size();
+            PAGE_SIZE.store(page_size, Ordering::Relaxed);
+            page_size
+        }
+        page_size => page_size,
+    }
+}
+/// Returns the granularity at which the kernel places new mappings, in bytes. Addresses of new [`Pages`], and offsets into mapped
+/// files, are multiples of this value. On windows this is usually larger than [`page_size`](64 KiB), on unix-like systems it is
+/// equal to [`page_size`]. The granularity is queried from the kernel once, and then cached.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// assert!(allocation_granularity().is_power_of_two());
+/// assert_eq!(allocation_granularity() % page_size(),0);
+/// ```
+#[must_use]
+pub fn allocation_granularity() -> usize {
+    match ALLOCATION_GRANULARITY.load(Ordering::Relaxed) {
+        0 => {
+            let granularity = query_allocation_granularity();
+            ALLOCATION_GRANULARITY.store(granularity, Ordering::Relaxed);
+            granularity
+        }
+        granularity => granularity,
+    }
+}
+#[cfg(target_family = "unix")]
+fn query_page_size() -> usize {
+    #[cfg(any(target_os = "linux", target_os = "redox"))]
+    const _SC_PAGESIZE: c_int = 30;
+    #[cfg(any(target_os = "solaris", target_os = "illumos"))]
+    const _SC_PAGESIZE: c_int = 11;
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    const _SC_PAGESIZE: c_int = 29;
+    #[cfg(target_os = "freebsd")]
+    const _SC_PAGESIZE: c_int = 47;
+    let page_size = unsafe { sysconf(_SC_PAGESIZE) };
+    assert!(page_size > 0, "Could not query the size of a page!");
+    page_size as usize
+}
+#[cfg(target_family = "unix")]
+fn query_allocation_granularity() -> usize {
+    page_size()
+}
+#[cfg(target_family = "windows")]
+fn system_info() -> winapi::um::sysinfoapi::SYSTEM_INFO {
+    let mut info = unsafe { std::mem::zeroed() };
+    unsafe { winapi::um::sysinfoapi::GetSystemInfo(&mut info) };
+    info
+}
+#[cfg(target_family = "windows")]
+fn query_page_size() -> usize {
+    system_info().dwPageSize as usize
+}
+#[cfg(target_family = "windows")]
+fn query_allocation_granularity() -> usize {
+    system_info().dwAllocationGranularity as usize
 }
-const PAGE_SIZE: usize = 0x1000;
 #[cfg(target_family = "unix")]
 const MAP_ANYNOMUS: c_int = 0x20;
 #[cfg(target_family = "unix")]
@@ -51,6 +213,12 @@ const MAP_PRIVATE: c_int = 0x2;
 #[cfg(target_family = "unix")]
 const NO_FILE: c_int = -1;
 #[cfg(target_family = "unix")]
+const MAP_FIXED: c_int = 0x10;
+#[cfg(target_os = "linux")]
+const MAP_NORESERVE: c_int = 0x4000;
+#[cfg(all(target_family = "unix", not(target_os = "linux")))]
+const MAP_NORESERVE: c_int = 0;
+#[cfg(target_family = "unix")]
 use std::ffi::{c_int, c_void};
 #[cfg(target_family = "unix")]
 extern "C" {
@@ -64,14 +232,41 @@ extern "C" {
     ) -> *mut c_void;
     fn munmap(addr: *mut c_void, length: usize) -> c_int;
     fn mprotect(addr: *mut c_void, len: usize, prot: c_int) -> c_int;
-    fn strerror(errnum: c_int) -> *const i8;
     fn mremap(old_addr: *mut c_void, old_size: usize, new_size: usize, flags: c_int)
         -> *mut c_void;
     fn posix_madvise(addr: *mut c_void, length: usize, advice: c_int) -> c_int;
+    #[cfg(target_os = "linux")]
+    fn madvise(addr: *mut c_void, length: usize, advice: c_int) -> c_int;
+    fn sysconf(name: c_int) -> std::ffi::c_long;
+    fn msync(addr: *mut c_void, length: usize, flags: c_int) -> c_int;
+    fn mlock(addr: *const c_void, length: usize) -> c_int;
+    #[cfg(target_os = "linux")]
+    fn mlock2(addr: *const c_void, length: usize, flags: std::ffi::c_uint) -> c_int;
+    fn munlock(addr: *const c_void, length: usize) -> c_int;
+    fn mincore(addr: *mut c_void, length: usize, vec: *mut u8) -> c_int;
+    #[cfg(target_os = "linux")]
+    fn syscall(number: std::ffi::c_long, ...) -> std::ffi::c_long;
+}
+/// Converts a combination of read `0x1`, write `0x2` and execute `0x4` bits into windows page protection constant.
+#[cfg(target_family = "windows")]
+fn fl_protect(mask: usize) -> u32 {
+    match mask {
+        0x0 => PAGE_NOACCESS,
+        0x1 => PAGE_READONLY,
+        0x2 => PAGE_READWRITE, //On windows, it is impossible to have a write-only page, but `Pages` must have
+        // AllowRead to be read from, so there are no issues here.
+        0x3 => PAGE_READWRITE,
+        0x4 => PAGE_EXECUTE,
+        0x5 => PAGE_EXECUTE_READ,
+        0x6 => PAGE_EXECUTE_READWRITE, //On windows, it is impossible to have a write but not read page, but `Pages` already
+        // must have AllowRead to be read from, so there are no issues here.
+        0x7 => PAGE_EXECUTE_READWRITE,
+        _ => panic!("Invalid protection mask:{mask}"),
+    }
 }
 /// Marks if a [`Pages`] can be read from.
 pub trait ReadPremisionMarker {
-    #[cfg(all(target_family = "unix"))]
+    #[cfg(target_family = "unix")]
     #[doc(hidden)]
     fn bitmask() -> c_int;
     #[doc(hidden)]
@@ -170,10 +365,30 @@ impl ExecPremisionMarker for DenyExec {
 pub struct Pages<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> {
     ptr: *mut u8,
     len: usize,
+    huge: Option<HugePageSize>,
+    guard: usize,
+    file: Option<MapMode>,
+    locked: bool,
+    /// Shared memory object backing this mapping, kept so that it can be mapped again.
+    #[cfg(target_family = "unix")]
+    fd: Option<std::sync::Arc<std::os::fd::OwnedFd>>,
     read: PhantomData<R>,
     write: PhantomData<W>,
     exec: PhantomData<E>,
 }
+// `Pages` owns the mapping it holds, and only hands out references to its memory through `&self`/`&mut self`, like a
+// `Vec<u8>` does. Memory may also be reachable through other mappings(of a file, a shared memory object, or views of
+// `DualMappedPages`), but all ways of creating those, or of accessing the object behind them(`Pages::shared_fd`), are
+// `unsafe`, and require the caller to ensure that memory is never changed through one mapping while it is borrowed through
+// another. Given that, it can be sent and shared between threads.
+unsafe impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Send
+    for Pages<R, W, E>
+{
+}
+unsafe impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Sync
+    for Pages<R, W, E>
+{
+}
 #[cfg(target_family = "unix")]
 fn erno() -> c_int {
     #[cfg(any(target_os = "linux", target_os = "redox"))]
@@ -198,11 +413,6 @@ fn erno() -> c_int {
         unsafe { *__error() }
     }
 }
-#[cfg(target_family = "unix")]
-fn errno_msg() -> String {
-    let cstr = unsafe { std::ffi::CStr::from_ptr(strerror(erno())) };
-    String::from_utf8_lossy(cstr.to_bytes()).to_string()
-}
 impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
     #[cfg(target_family = "unix")]
     fn bitmask() -> c_int {
@@ -210,40 +420,26 @@ impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pa
     }
     #[cfg(target_family = "windows")]
     fn flProtect() -> u32 {
-        let mask = (R::allow_read() as u8 * 0x1)
-            | (W::allow_write() as u8 * 0x2)
-            | (E::allow_exec() as u8 * 0x4);
-        match mask {
-            0x0 => PAGE_NOACCESS,
-            0x1 => PAGE_READONLY,
-            0x2 => PAGE_READWRITE, //On windows, it is impossible to have a write-only page, but `Pages` must have
-            // AllowRead to be read from, so there are no issues here.
-            0x3 => PAGE_READWRITE,
-            0x4 => PAGE_EXECUTE,
-            0x5 => PAGE_EXECUTE_READ,
-            0x6 => PAGE_EXECUTE_READWRITE, //On windows, it is impossible to have a write but not read page, but `Pages` already
-            // must have AllowRead to be read from, so there are no issues here.
-            0x7 => PAGE_EXECUTE_READWRITE,
-            0x8..=0xFF => panic!("Invalid protection mask:{mask}"),
-        }
+        fl_protect(Self::protection_index())
     }
     /// Allocates new [`Pages`] of size at least length, rounded up to next Page boundary if necessary.
     /// # Panics
     /// Panics when a 0-sized allocation is attempted, or if kernel can't/refuses to allocate requested Pages(Should never happen).
+    /// Use [`Self::try_new`] to handle allocation failures instead.
     /// # Examples
     /// Allocating pages works with sizes divisible by size of the page:
     ///```
     /// # use memory_pages::*;
-    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x8000);
-    /// assert_eq!(memory.len(),0x8000);
+    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(8 * page_size());
+    /// assert_eq!(memory.len(),8 * page_size());
     ///```
     /// And allocation sized not divisible by the size of the page:
     ///```
     /// # use memory_pages::*;
-    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1234);
+    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(page_size() + 0x234);
     /// // Rounds up to the next page boundary, so that length of the actual allocation
     /// // may never be less than requested length.
-    /// assert_eq!(memory.len(),0x2000);
+    /// assert_eq!(memory.len(),2 * page_size());
     ///```
     /// 0-sized allocations will always fail.
     /// ```should_panic
@@ -252,6 +448,22 @@ impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pa
     ///```
     #[must_use]
     pub fn new(length: usize) -> Self {
+        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+        Self::try_new(length).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Allocates new [`Pages`] of size at least length, rounded up to next Page boundary if necessary. Works like
+    /// [`Self::new`], but returns a [`PagesError`] if the kernel can't/refuses to allocate requested Pages, instead of panicking.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if the allocation fails, including 0-sized allocations.
+    /// # Examples
+    ///```
+    /// # use memory_pages::*;
+    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::try_new(page_size() + 0x234).unwrap();
+    /// assert_eq!(memory.len(),2 * page_size());
+    /// // 0-sized allocations return an error.
+    /// assert!(Pages::<AllowRead,AllowWrite,DenyExec>::try_new(0).is_err());
+    ///```
+    pub fn try_new(length: usize) -> Result<Self, PagesError> {
         Self::new_native(length)
     }
     /// Advises this [`Pages`] that `used` bytes are going to be in use soon.
@@ -288,104 +500,174 @@ impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pa
             posix_madvise(self.ptr as *mut c_void, self.len, POSIX_MADV_RANDOM);
         }
     }
-    #[cfg(target_family = "windows")]
-    fn new_native(length: usize) -> Self {
-        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
-        let len = next_page_boundary(length);
-        let ptr =
-            unsafe { VirtualAlloc(std::ptr::null_mut(), length, MEM_COMMIT, Self::flProtect()) }
-                .cast::<u8>();
-        if ptr.is_null(){
-            let err = unsafe { winapi::um::errhandlingapi::GetLastError() };
-            panic!("Allocation using VirtualAlloc failed with error code:{err}!");
-        }
+    /// Creates [`Pages`] owning the mapping at `ptr` of size `len`.
+    fn from_raw_parts(ptr: *mut u8, len: usize) -> Self {
+        stats::record_map(Self::protection_index(), len);
+        #[cfg(any(feature = "track_mappings", doc, test))]
+        registry::register(ptr, len, Self::protection_index());
         Self {
             ptr,
             len,
+            huge: None,
+            guard: 0,
+            file: None,
+            locked: false,
+            #[cfg(target_family = "unix")]
+            fd: None,
             read: PhantomData,
             write: PhantomData,
             exec: PhantomData,
         }
     }
-    #[cfg(target_family = "unix")]
-    fn new_native(length: usize) -> Self {
-        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+    /// Gives up ownership of the mapping, without releasing it.
+    fn into_raw_parts(self) -> (*mut u8, usize) {
+        stats::record_unmap(Self::protection_index(), self.len);
+        #[cfg(any(feature = "track_mappings", doc, test))]
+        registry::unregister(self.ptr);
+        let parts = (self.ptr, self.len);
+        std::mem::forget(self);
+        parts
+    }
+    /// Changes the length of the mapping owned by `self`, after it was split or joined in place.
+    fn set_len(&mut self, len: usize) {
+        stats::record_resize(Self::protection_index(), self.len, len);
+        #[cfg(any(feature = "track_mappings", doc, test))]
+        registry::set_len(self.ptr, len);
+        self.len = len;
+    }
+    /// Moves the mapping from permission combination `from` to `to` in statistics and the registry of live mappings.
+    fn record_protection(&self, from: usize, to: usize) {
+        stats::record_cast(from, to, self.len);
+        #[cfg(any(feature = "track_mappings", doc, test))]
+        registry::set_protection(self.ptr, to);
+    }
+    /// Changes the permission markers of `self`, without changing the actual permissions of the mapping.
+    fn cast_prot<TR: ReadPremisionMarker, TW: WritePremisionMarker, TE: ExecPremisionMarker>(
+        mut self,
+    ) -> Pages<TR, TW, TE> {
+        self.record_protection(
+            Self::protection_index(),
+            Pages::<TR, TW, TE>::protection_index(),
+        );
+        let res = Pages {
+            ptr: self.ptr,
+            len: self.len,
+            huge: self.huge,
+            guard: self.guard,
+            file: self.file,
+            locked: self.locked,
+            #[cfg(target_family = "unix")]
+            fd: self.fd.take(),
+            read: PhantomData,
+            write: PhantomData,
+            exec: PhantomData,
+        };
+        std::mem::forget(self);
+        res
+    }
+    /// Size of the pages backing this mapping.
+    fn granularity(&self) -> usize {
+        self.huge.map_or_else(page_size, HugePageSize::bytes)
+    }
+    #[cfg(target_family = "windows")]
+    fn new_native(length: usize) -> Result<Self, PagesError> {
         let len = next_page_boundary(length);
+        let ptr = unsafe { VirtualAlloc(std::ptr::null_mut(), len, MEM_COMMIT, Self::flProtect()) }
+            .cast::<u8>();
+        if ptr.is_null() {
+            return Err(PagesError::last_os_error(PagesOperation::Map));
+        }
+        Ok(Self::from_raw_parts(ptr, len))
+    }
+    #[cfg(target_family = "unix")]
+    fn new_native(length: usize) -> Result<Self, PagesError> {
+        Self::mmap_anonymous(next_page_boundary(length), 0)
+    }
+    /// Maps `len` bytes of anonymous memory, with `flags` added to default mapping flags.
+    #[cfg(target_family = "unix")]
+    fn mmap_anonymous(len: usize, flags: c_int) -> Result<Self, PagesError> {
         let prot_mask = Self::bitmask();
         let ptr = unsafe {
             mmap(
                 std::ptr::null_mut(),
                 len,
                 prot_mask,
-                MAP_ANYNOMUS | MAP_PRIVATE,
+                MAP_ANYNOMUS | MAP_PRIVATE | flags,
                 NO_FILE,
                 0,
             )
         }
         .cast::<u8>();
         if ptr as usize == usize::MAX {
-            let erno = errno_msg();
-            panic!("mmap error, erno:{erno:?}!");
-        }
-        Self {
-            ptr,
-            len,
-            read: PhantomData,
-            write: PhantomData,
-            exec: PhantomData,
+            return Err(PagesError::last_os_error(PagesOperation::Map));
         }
+        Ok(Self::from_raw_parts(ptr, len))
+    }
+    fn set_prot(&mut self) -> Result<(), PagesError> {
+        self.protect(Self::protection_index())
     }
+    /// Changes the permissions of the mapping to `protection`(a combination of read `0x1`, write `0x2` and execute `0x4`
+    /// bits), without changing the permission markers.
     #[cfg(target_family = "unix")]
-    fn set_prot(&mut self) {
-        let mask = Self::bitmask();
-        if unsafe { mprotect(self.ptr.cast::<c_void>(), self.len, mask) } != -1 && erno() != 0 {
-            let err = errno_msg();
-            panic!("Failed to change memory protection mode:'{err}'!");
+    fn protect(&mut self, protection: usize) -> Result<(), PagesError> {
+        // Those bits have the same values as `PROT_READ`, `PROT_WRITE` and `PROT_EXEC`.
+        let mask = protection as c_int;
+        if unsafe { mprotect(self.ptr.cast::<c_void>(), self.len, mask) } == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Protect));
         }
+        Ok(())
     }
     #[cfg(target_family = "windows")]
-    fn set_prot(&mut self) {
+    fn protect(&mut self, protection: usize) -> Result<(), PagesError> {
         let mut _old: u32 = 0;
         let res = unsafe {
             winapi::um::memoryapi::VirtualProtect(
                 self.ptr.cast::<winapi::ctypes::c_void>(),
                 self.len,
-                Self::flProtect(),
+                fl_protect(protection),
                 &mut _old as *mut _,
             )
         };
         if res == 0 {
-            let err = unsafe { winapi::um::errhandlingapi::GetLastError() };
-            panic!("Changing memory protection using using VirtualProtect failed with error code:{err}!");
+            return Err(PagesError::last_os_error(PagesOperation::Protect));
         }
+        Ok(())
     }
     fn into_prot<TR: ReadPremisionMarker, TW: WritePremisionMarker, TE: ExecPremisionMarker>(
         self,
     ) -> Pages<TR, TW, TE> {
-        let mut res = Pages {
-            ptr: self.ptr,
-            len: self.len,
-            read: PhantomData,
-            write: PhantomData,
-            exec: PhantomData,
-        };
-        std::mem::forget(self);
+        self.try_into_prot()
+            .unwrap_or_else(|(_, err)| panic!("{err}"))
+    }
+    fn try_into_prot<TR: ReadPremisionMarker, TW: WritePremisionMarker, TE: ExecPremisionMarker>(
+        self,
+    ) -> Result<Pages<TR, TW, TE>, (Self, PagesError)> {
         #[cfg(target_family = "unix")]
-        if Self::bitmask() == (Pages::<TR, TW, TE>::bitmask()) {
-            return res;
-        }
+        let same_prot = Self::bitmask() == Pages::<TR, TW, TE>::bitmask();
         #[cfg(target_family = "windows")]
-        if Self::flProtect() == (Pages::<TR, TW, TE>::flProtect()) {
-            return res;
+        let same_prot = Self::flProtect() == Pages::<TR, TW, TE>::flProtect();
+        let mut res = self.cast_prot();
+        if !same_prot {
+            if let Err(err) = res.set_prot() {
+                // Permissions were left unchanged, so the original markers still describe the mapping.
+                return Err((res.cast_prot(), err));
+            }
         }
-        res.set_prot();
-        res
+        Ok(res)
     }
-    /// Releases physical memory pages behind the region starting at page `beginning` is in, and continuing till page `beginning + length` is in. Those pages will be given backing the next time they are accessed.
+    /// Releases physical memory pages lying fully inside the region from `beginning` to `beginning + length`. Pages only
+    /// partially covered by the region(or, for huge [`Pages`], huge pages) are left untouched, so data outside of the
+    /// region is always preserved. Released pages will be given backing the next time they are accessed.
     /// # Beware
-    /// After calling `decommit` data inside those pages will be wiped and then the content of those pages will be implementation dependent and should not be relied upon to be 0.
+    /// After calling `decommit` data inside released pages will be wiped and then the content of those pages will be implementation dependent and should not be relied upon to be 0.
     pub fn decommit(&mut self, beginning: usize, length: usize) {
-        let decommit_len = length.min(self.len - beginning);
+        let granularity = self.granularity();
+        let end = prev_boundary(beginning.saturating_add(length).min(self.len), granularity);
+        let beginning = next_boundary(beginning.min(self.len), granularity);
+        if end <= beginning {
+            return;
+        }
+        let decommit_len = end - beginning;
         #[cfg(target_os = "windows")]
         unsafe {
             let res = DiscardVirtualMemory(
@@ -396,7 +678,17 @@ impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pa
                 panic!("DiscardVirtualMemory failed.");
             }
         }
-        #[cfg(target_family = "unix")]
+        // `posix_madvise` ignores `POSIX_MADV_DONTNEED` on linux, so pages would never be released.
+        #[cfg(target_os = "linux")]
+        unsafe {
+            const MADV_DONTNEED: c_int = 4;
+            madvise(
+                (self.ptr as usize + beginning) as *mut c_void,
+                decommit_len,
+                MADV_DONTNEED,
+            );
+        }
+        #[cfg(all(target_family = "unix", not(target_os = "linux")))]
         unsafe {
             const MADV_DONTNEED: c_int = 4;
             posix_madvise(
@@ -408,7 +700,7 @@ impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pa
     }
 }
 impl<E: ExecPremisionMarker> Pages<AllowRead, AllowWrite, E> {
-    /// Changes the size of this [`Pages`]
+    /// Changes the size of this [`Pages`], rounding `new_size` up to the next page boundary if necessary.
     /// # Waring
     /// ## Pointer invalidation
     /// *Rust mutable borrow rules prevent this from happening in safe code. This section only concerns pointers to
@@ -427,26 +719,66 @@ impl<E: ExecPremisionMarker> Pages<AllowRead, AllowWrite, E> {
     /// assert!(prev_len < pages.len());
     /// ```
     pub fn resize(&mut self, new_size: usize) {
+        self.try_resize(new_size)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+    /// Changes the size of this [`Pages`]. Works like [`Self::resize`], but returns a [`PagesError`] instead of panicking.
+    /// If resizing fails, `self` is left unchanged.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Remap`](or [`PagesOperation::Map`] on systems without `mremap`) if
+    /// the kernel can't/refuses to resize this [`Pages`]. [`Pages`] mapping a file can't be resized, since memory past the
+    /// end of the file can't be accessed.
+    /// # Example
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut pages:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1_000);
+    /// pages[0] = 123;
+    /// pages.try_resize(0x10_000).unwrap();
+    /// assert_eq!(pages.len(),0x10_000);
+    /// assert_eq!(pages[0],123);
+    /// // Resizing to 0 bytes fails, and leaves `pages` untouched.
+    /// assert!(pages.try_resize(0).is_err());
+    /// assert_eq!(pages.len(),0x10_000);
+    /// ```
+    pub fn try_resize(&mut self, new_size: usize) -> Result<(), PagesError> {
+        // Growing past the end of the file would make accessing new memory raise SIGBUS, and copying would silently detach
+        // the data from the file.
+        if self.file.is_some() {
+            return Err(PagesError::invalid_argument(PagesOperation::Remap));
+        }
+        let new_size = next_boundary(new_size, self.granularity());
+        // Guard regions have different permissions than the usable region, so they can't be remapped together with it.
         #[cfg(target_family = "unix")]
-        unsafe {
+        if self.guard == 0 {
             const MREMAP_MAYMOVE: c_int = 1;
-            let ptr = mremap(self.ptr as *mut c_void, self.len, new_size, MREMAP_MAYMOVE);
+            // Once moved, the old address may be reused by another thread right away, so the entry is taken out first.
+            #[cfg(any(feature = "track_mappings", doc, test))]
+            let entry = registry::take(self.ptr);
+            let ptr =
+                unsafe { mremap(self.ptr as *mut c_void, self.len, new_size, MREMAP_MAYMOVE) };
             if ptr as usize == usize::MAX {
-                let erno = errno_msg();
-                panic!("mmap error, erno:{erno:?}!");
+                let err = PagesError::last_os_error(PagesOperation::Remap);
+                #[cfg(any(feature = "track_mappings", doc, test))]
+                registry::restore(self.ptr, self.len, entry);
+                return Err(err);
             }
-            self.ptr = ptr as *mut u8;
+            #[cfg(any(feature = "track_mappings", doc, test))]
+            registry::restore(ptr.cast::<u8>(), new_size, entry);
+            stats::record_resize(Self::protection_index(), self.len, new_size);
+            self.ptr = ptr.cast::<u8>();
             self.len = new_size;
+            return Ok(());
         }
-        #[cfg(not(target_family = "unix"))]
-        {
-            let mut copy = Self::new(new_size);
-            let copy_size = copy.len().min(self.len());
-            copy.split_at_mut(copy_size)
-                .0
-                .copy_from_slice(self.split_at_mut(copy_size).0);
-            *self = copy;
+        let mut copy = Self::try_new_guarded(new_size, self.guard)?;
+        if self.locked {
+            copy.try_lock()?;
         }
+        let copy_size = copy.len().min(self.len());
+        copy.split_at_mut(copy_size)
+            .0
+            .copy_from_slice(self.split_at_mut(copy_size).0);
+        *self = copy;
+        Ok(())
     }
 }
 impl<W: WritePremisionMarker, E: ExecPremisionMarker> std::ops::Index<usize>
@@ -595,6 +927,79 @@ impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pa
         self.into_prot()
     }
 }
+/// Fallible permission changes. Each of those functions works like its counterpart without the `try_` prefix, but returns
+/// the original, unchanged [`Pages`] together with a [`PagesError`] if the kernel refuses to change the permissions, instead
+/// of panicking.
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Fallible version of [`Self::allow_read`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let memory:Pages<DenyRead,DenyWrite,DenyExec> = Pages::new(0x1000);
+    /// let memory = match memory.try_allow_read() {
+    ///     Ok(memory) => memory,
+    ///     // Permissions are unchanged, and `memory` can still be used.
+    ///     Err((_memory, err)) => panic!("{err}"),
+    /// };
+    /// assert_eq!(memory[0], 0);
+    /// ```
+    pub fn try_allow_read(self) -> Result<Pages<AllowRead, W, E>, (Self, PagesError)> {
+        self.try_into_prot()
+    }
+    /// Fallible version of [`Self::deny_read`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    pub fn try_deny_read(self) -> Result<Pages<DenyRead, W, E>, (Self, PagesError)> {
+        self.try_into_prot()
+    }
+    /// Fallible version of [`Self::allow_write`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    pub fn try_allow_write(self) -> Result<Pages<R, AllowWrite, E>, (Self, PagesError)> {
+        self.try_into_prot()
+    }
+    /// Fallible version of [`Self::deny_write`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    pub fn try_deny_write(self) -> Result<Pages<R, DenyWrite, E>, (Self, PagesError)> {
+        self.try_into_prot()
+    }
+    /// Fallible version of [`Self::allow_write_no_exec`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    pub fn try_allow_write_no_exec(
+        self,
+    ) -> Result<Pages<R, AllowWrite, DenyExec>, (Self, PagesError)> {
+        self.try_into_prot()
+    }
+    /// Fallible version of [`Self::allow_exec`].
+    /// # Safety
+    /// Same as [`Self::allow_exec`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    #[cfg(any(feature = "allow_exec", doc, test))]
+    pub fn try_allow_exec(self) -> Result<Pages<R, W, AllowExec>, (Self, PagesError)> {
+        self.try_into_prot()
+    }
+    /// Fallible version of [`Self::set_protected_exec`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    #[cfg(any(feature = "allow_exec", doc, test))]
+    pub fn try_set_protected_exec(
+        self,
+    ) -> Result<Pages<R, DenyWrite, AllowExec>, (Self, PagesError)> {
+        self.try_into_prot()
+    }
+    /// Fallible version of [`Self::deny_exec`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    #[cfg(any(feature = "allow_exec", doc, test))]
+    pub fn try_deny_exec(self) -> Result<Pages<R, W, DenyExec>, (Self, PagesError)> {
+        self.try_into_prot()
+    }
+}
 impl<W: WritePremisionMarker, E: ExecPremisionMarker> Pages<AllowRead, W, E> {
     /// Sets the [`AllowRead`], making data inside page readable.
     /// # Panics
@@ -684,9 +1089,9 @@ impl<R: ReadPremisionMarker, W: WritePremisionMarker> Pages<R, W, AllowExec> {
     /// unsafe{assert_eq!(add.call((43,34)),77)};
     /// ```
     #[must_use]
-    pub unsafe fn get_fn<F: ExternFnPtr>(&self, offset: usize) -> FnRef<F>
+    pub unsafe fn get_fn<F>(&self, offset: usize) -> FnRef<'_, F>
     where
-        F: Copy + Pointer + Sized,
+        F: ExternFnPtr + Copy + Pointer + Sized,
     {
         let fn_ptr = self.get_fn_ptr(offset);
         let f: F = *(std::ptr::addr_of!(fn_ptr).cast::<F>());
@@ -694,24 +1099,39 @@ impl<R: ReadPremisionMarker, W: WritePremisionMarker> Pages<R, W, AllowExec> {
         FnRef::new(f, self)
     }
 }
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> std::fmt::Debug
+    for Pages<R, W, E>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        // Contents may be unreadable, so only the location of the mapping is printed.
+        f.debug_struct("Pages")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
 impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Drop
     for Pages<R, W, E>
 {
     fn drop(&mut self) {
+        stats::record_unmap(Self::protection_index(), self.len);
+        #[cfg(any(feature = "track_mappings", doc, test))]
+        registry::unregister(self.ptr);
+        // Failing to release pages only leaks address space, so it is not worth panicking(and possibly aborting) over.
+        if self.locked {
+            let _ = self.try_unlock();
+        }
+        let base = self.ptr.wrapping_sub(self.guard);
         #[cfg(target_family = "unix")]
         unsafe {
-            let res = munmap(self.ptr.cast::<c_void>(), self.len);
-            if res == -1 {
-                let err = errno_msg();
-                panic!("Unampping memory Pages failed. Reason:{err}");
-            }
+            munmap(base.cast::<c_void>(), self.len + 2 * self.guard);
         }
         #[cfg(target_family = "windows")]
         unsafe {
-            let res = VirtualFree(self.ptr.cast::<winapi::ctypes::c_void>(), 0, MEM_RELEASE);
-            if res == 0 {
-                let err = winapi::um::errhandlingapi::GetLastError();
-                panic!("Allocation using VirtualFree failed with error code:{err}!");
+            if self.file.is_some() {
+                UnmapViewOfFile(base.cast::<winapi::ctypes::c_void>());
+            } else {
+                VirtualFree(base.cast::<winapi::ctypes::c_void>(), 0, MEM_RELEASE);
             }
         }
     }
@@ -795,7 +1215,7 @@ mod test {
     fn test_allow_read() {
         let pages: Pages<DenyRead, DenyWrite, DenyExec> = Pages::new(256);
         let pages = pages.allow_read();
-        let rf: &[u8] = &pages;
+        let _rf: &[u8] = &pages;
     }
     #[test]
     fn test_allow_write() {
@@ -805,6 +1225,62 @@ mod test {
         assert_eq!(pages[0], 243);
     }
     #[test]
+    fn test_page_size() {
+        let page_size = page_size();
+        assert!(page_size.is_power_of_two());
+        assert_eq!(page_size, super::page_size());
+        assert_eq!(next_page_boundary(1), page_size);
+        assert_eq!(next_page_boundary(page_size), page_size);
+        assert_eq!(prev_boundary(page_size + 1, page_size), page_size);
+        let pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(page_size + 1);
+        assert_eq!(pages.len(), 2 * page_size);
+        assert_eq!(pages.ptr as usize % allocation_granularity(), 0);
+    }
+    #[test]
+    fn test_decommit_unaligned() {
+        let page_size = page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(3 * page_size);
+        pages[0] = 1;
+        pages[page_size] = 2;
+        pages[3 * page_size - 1] = 3;
+        // No page lies fully inside the range, so nothing is released, even the page the range is in.
+        pages.decommit(page_size + 1, page_size - 2);
+        assert_eq!(pages[page_size], 2);
+        // Only the middle page lies fully inside, so data in first and last pages must be preserved.
+        pages.decommit(page_size - 1, page_size + 2);
+        assert_eq!(pages[0], 1);
+        assert_eq!(pages[3 * page_size - 1], 3);
+        // Out of bounds ranges are clamped.
+        pages.decommit(2 * page_size, usize::MAX);
+        pages.decommit(4 * page_size, 1);
+    }
+    #[test]
+    fn test_try_new_zero() {
+        let err = Pages::<AllowRead, AllowWrite, DenyExec>::try_new(0).unwrap_err();
+        assert_eq!(err.operation(), PagesOperation::Map);
+        assert_ne!(err.raw_os_error(), 0);
+    }
+    #[test]
+    fn test_try_deny_write() {
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::try_new(256).unwrap();
+        pages[0] = 12;
+        let pages = pages.try_deny_write().unwrap();
+        assert_eq!(pages[0], 12);
+    }
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn test_try_prot_failure_returns_pages() {
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(0x1000);
+        pages[0] = 42;
+        // Misalign the pointer, so that `mprotect` rejects it with EINVAL.
+        pages.ptr = unsafe { pages.ptr.add(1) };
+        let (mut pages, err) = pages.try_deny_write().unwrap_err();
+        assert_eq!(err.operation(), PagesOperation::Protect);
+        pages.ptr = unsafe { pages.ptr.sub(1) };
+        pages[0] += 1;
+        assert_eq!(pages[0], 43);
+    }
+    #[test]
     #[cfg(target_arch = "x86_64")]
     #[cfg(feature = "allow_exec")]
     fn test_allow_exec() {
@@ -0,0 +1,188 @@
+use crate::*;
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Allocates new [`Pages`] of size at least `length`, rounded up to next page boundary if necessary, and locks them in
+    /// physical memory, like [`Self::lock`] does.
+    /// # Panics
+    /// Panics when a 0-sized allocation is attempted, if kernel can't/refuses to allocate requested Pages, or if they can't
+    /// be locked.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = match Pages::try_new_locked(0x1000){
+    ///     Ok(memory)=>memory,
+    ///     // Locking is limited, and may not be allowed at all.
+    ///     Err(err) if err.is_lock_limit_exceeded()=>return,
+    ///     Err(err)=>panic!("{err}"),
+    /// };
+    /// assert!(memory.is_locked());
+    /// memory[0] = 1;
+    /// ```
+    #[must_use]
+    pub fn new_locked(length: usize) -> Self {
+        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+        Self::try_new_locked(length).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new_locked`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if the allocation fails, including 0-sized allocations, or with
+    /// [`PagesOperation::Lock`] if the pages could not be locked.
+    pub fn try_new_locked(length: usize) -> Result<Self, PagesError> {
+        let mut pages = Self::try_new(length)?;
+        pages.try_lock()?;
+        Ok(pages)
+    }
+    /// Locks this [`Pages`] in physical memory, faulting all of them in, and preventing them from being swapped out until
+    /// they are unlocked or dropped. Accessing locked pages never causes a page fault. Locks are not nested: unlocking
+    /// once undoes any number of locks.
+    /// # Panics
+    /// Panics if the pages can't be locked, e.g. because the limit of locked memory would be exceeded.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1000);
+    /// if memory.try_lock().is_ok(){
+    ///     assert!(memory.is_locked());
+    ///     memory.unlock();
+    /// }
+    /// assert!(!memory.is_locked());
+    /// ```
+    pub fn lock(&mut self) {
+        self.try_lock().unwrap_or_else(|err| panic!("{err}"));
+    }
+    /// Fallible version of [`Self::lock`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Lock`] if the pages could not be locked. Use
+    /// [`PagesError::is_lock_limit_exceeded`] to check if this happened because the limit of locked memory(`RLIMIT_MEMLOCK`)
+    /// would be exceeded.
+    pub fn try_lock(&mut self) -> Result<(), PagesError> {
+        #[cfg(target_family = "unix")]
+        let failed = unsafe { mlock(self.ptr as *const c_void, self.len) } == -1;
+        #[cfg(target_family = "windows")]
+        let failed =
+            unsafe { VirtualLock(self.ptr.cast::<winapi::ctypes::c_void>(), self.len) } == 0;
+        if failed {
+            return Err(PagesError::last_os_error(PagesOperation::Lock));
+        }
+        self.locked = true;
+        Ok(())
+    }
+    /// Locks this [`Pages`] in physical memory, like [`Self::lock`] does, but without faulting them in: each page is locked
+    /// when it is first accessed. This avoids paying for pages which are never used. Pages which are known to be used
+    /// soon can still be faulted in ahead of time using [`Self::advise_use_soon`].
+    /// # Panics
+    /// Panics if the pages can't be locked, e.g. because the limit of locked memory would be exceeded.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x10000);
+    /// if memory.try_lock_on_fault().is_ok(){
+    ///     // Only the first page is going to be used right away.
+    ///     memory.advise_use_soon(0x1000);
+    ///     memory[0] = 1;
+    /// }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn lock_on_fault(&mut self) {
+        self.try_lock_on_fault()
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
+    /// Fallible version of [`Self::lock_on_fault`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Lock`] if the pages could not be locked, same as [`Self::try_lock`].
+    #[cfg(target_os = "linux")]
+    pub fn try_lock_on_fault(&mut self) -> Result<(), PagesError> {
+        const MLOCK_ONFAULT: std::ffi::c_uint = 0x1;
+        if unsafe { mlock2(self.ptr as *const c_void, self.len, MLOCK_ONFAULT) } == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Lock));
+        }
+        self.locked = true;
+        Ok(())
+    }
+    /// Unlocks this [`Pages`], allowing them to be swapped out again. Does nothing if they are not locked.
+    /// # Panics
+    /// Panics if the kernel refuses to unlock the pages(should never happen).
+    pub fn unlock(&mut self) {
+        self.try_unlock().unwrap_or_else(|err| panic!("{err}"));
+    }
+    /// Fallible version of [`Self::unlock`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Unlock`] if the kernel refuses to unlock the pages.
+    pub fn try_unlock(&mut self) -> Result<(), PagesError> {
+        if !self.locked {
+            return Ok(());
+        }
+        #[cfg(target_family = "unix")]
+        let failed = unsafe { munlock(self.ptr as *const c_void, self.len) } == -1;
+        #[cfg(target_family = "windows")]
+        let failed =
+            unsafe { VirtualUnlock(self.ptr.cast::<winapi::ctypes::c_void>(), self.len) } == 0;
+        if failed {
+            return Err(PagesError::last_os_error(PagesOperation::Unlock));
+        }
+        self.locked = false;
+        Ok(())
+    }
+    /// Returns `true` if this [`Pages`] is locked in physical memory.
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_lock_unlock() {
+        let page_size = page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(2 * page_size);
+        assert!(!pages.is_locked());
+        match pages.try_lock() {
+            Ok(()) => {}
+            Err(err) if err.is_lock_limit_exceeded() => return,
+            Err(err) => panic!("{err}"),
+        }
+        assert!(pages.is_locked());
+        pages[0] = 1;
+        // Locking survives changes of permissions and size.
+        let mut pages = pages.deny_write().allow_write();
+        assert!(pages.is_locked());
+        pages.resize(4 * page_size);
+        assert!(pages.is_locked());
+        assert_eq!(pages[0], 1);
+        pages.unlock();
+        assert!(!pages.is_locked());
+        pages.unlock();
+    }
+    #[test]
+    fn test_lock_guarded_resize() {
+        let page_size = page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> =
+            Pages::new_guarded(page_size, page_size);
+        if pages.try_lock().is_err() {
+            return;
+        }
+        pages[0] = 2;
+        pages.resize(2 * page_size);
+        assert!(pages.is_locked());
+        assert_eq!(pages[0], 2);
+    }
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_lock_on_fault() {
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(0x10000);
+        if pages.try_lock_on_fault().is_ok() {
+            assert!(pages.is_locked());
+            pages.advise_use_soon(0x1000);
+            pages[0] = 1;
+        }
+    }
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn test_lock_limit_error() {
+        const ENOMEM: i32 = 12;
+        let err = PagesError::new(PagesOperation::Lock, ENOMEM);
+        assert!(err.is_lock_limit_exceeded());
+        assert!(err.to_string().contains("RLIMIT_MEMLOCK"));
+        assert!(!PagesError::new(PagesOperation::Map, ENOMEM).is_lock_limit_exceeded());
+    }
+}
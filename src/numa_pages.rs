@@ -0,0 +1,243 @@
+use crate::*;
+use std::ffi::{c_long, c_ulong};
+#[cfg(target_arch = "x86_64")]
+mod syscalls {
+    pub const MBIND: std::ffi::c_long = 237;
+    pub const SET_MEMPOLICY: std::ffi::c_long = 238;
+    pub const MOVE_PAGES: std::ffi::c_long = 279;
+}
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+mod syscalls {
+    pub const MBIND: std::ffi::c_long = 235;
+    pub const SET_MEMPOLICY: std::ffi::c_long = 237;
+    pub const MOVE_PAGES: std::ffi::c_long = 239;
+}
+const MPOL_DEFAULT: c_long = 0;
+const MPOL_PREFERRED: c_long = 1;
+const MPOL_BIND: c_long = 2;
+const MPOL_INTERLEAVE: c_long = 3;
+const MPOL_MF_MOVE: c_long = 1 << 1;
+/// Describes on which NUMA nodes physical memory backing [`Pages`] should be placed. A policy only affects pages which are
+/// not yet backed by physical memory: use [`Pages::migrate_to_node`] to move existing ones. Nodes are numbered like in
+/// `/sys/devices/system/node`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NumaPolicy {
+    /// Use the policy of the thread touching the memory first, or the system default(the node of the CPU running that
+    /// thread) if it has none.
+    Default,
+    /// Place memory only on the listed nodes. Allocations fail(causing a `SIGBUS`, or waking the OOM killer) if they are
+    /// out of memory.
+    Bind(Vec<usize>),
+    /// Spread memory page by page across the listed nodes, balancing bandwidth between them.
+    Interleave(Vec<usize>),
+    /// Place memory on the given node if possible, falling back to other nodes when it is out of memory.
+    Preferred(usize),
+}
+impl NumaPolicy {
+    /// Returns the mode of this policy, and the mask of nodes it uses.
+    fn mode_and_mask(&self) -> (c_long, Vec<c_ulong>) {
+        let (mode, nodes) = match self {
+            Self::Default => (MPOL_DEFAULT, &[][..]),
+            Self::Bind(nodes) => (MPOL_BIND, &nodes[..]),
+            Self::Interleave(nodes) => (MPOL_INTERLEAVE, &nodes[..]),
+            Self::Preferred(node) => (MPOL_PREFERRED, std::slice::from_ref(node)),
+        };
+        const BITS: usize = c_ulong::BITS as usize;
+        let words = nodes.iter().max().map_or(0, |max| max / BITS + 1);
+        let mut mask = vec![0; words];
+        for node in nodes {
+            mask[node / BITS] |= 1 << (node % BITS);
+        }
+        (mode, mask)
+    }
+    /// Sets this policy as the default for all memory touched for the first time by the calling thread, including
+    /// memory not managed by this crate. [`Pages`] with their own policy are not affected.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Advise`] if any of the nodes does not exist, or if the kernel does not
+    /// support NUMA.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// NumaPolicy::Preferred(0).apply_to_current_thread().unwrap();
+    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1000);
+    /// NumaPolicy::Default.apply_to_current_thread().unwrap();
+    /// ```
+    pub fn apply_to_current_thread(&self) -> Result<(), PagesError> {
+        let (mode, mask) = self.mode_and_mask();
+        let res = unsafe {
+            syscall(
+                syscalls::SET_MEMPOLICY,
+                mode,
+                mask_ptr(&mask),
+                max_node(&mask),
+            )
+        };
+        if res == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Advise));
+        }
+        Ok(())
+    }
+}
+fn mask_ptr(mask: &[c_ulong]) -> *const c_ulong {
+    if mask.is_empty() {
+        std::ptr::null()
+    } else {
+        mask.as_ptr()
+    }
+}
+fn max_node(mask: &[c_ulong]) -> c_ulong {
+    // The kernel ignores the last bit of the mask, so it has to be one bit longer than needed.
+    match mask.len() {
+        0 => 0,
+        words => (words * c_ulong::BITS as usize + 1) as c_ulong,
+    }
+}
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Allocates new [`Pages`] of size at least `length`, rounded up to next page boundary if necessary, with physical
+    /// memory backing them placed according to `policy`.
+    /// # Panics
+    /// Panics when a 0-sized allocation is attempted, if kernel can't/refuses to allocate requested Pages, or if `policy`
+    /// can't be applied.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new_numa(0x4000, &NumaPolicy::Bind(vec![0]));
+    /// memory[0] = 1;
+    /// assert_eq!(memory.page_nodes()[0], Some(0));
+    /// ```
+    #[must_use]
+    pub fn new_numa(length: usize, policy: &NumaPolicy) -> Self {
+        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+        Self::try_new_numa(length, policy).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new_numa`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if the allocation fails, including 0-sized allocations, or with
+    /// [`PagesOperation::Advise`] if `policy` can't be applied.
+    pub fn try_new_numa(length: usize, policy: &NumaPolicy) -> Result<Self, PagesError> {
+        let mut pages = Self::try_new(length)?;
+        pages.set_numa_policy(policy)?;
+        Ok(pages)
+    }
+    /// Sets the policy used to place physical memory backing pages of this [`Pages`] which are touched for the first time
+    /// from now on. Pages already backed by physical memory stay where they are.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Advise`] if any of the nodes does not exist, or if the kernel does not
+    /// support NUMA.
+    pub fn set_numa_policy(&mut self, policy: &NumaPolicy) -> Result<(), PagesError> {
+        self.mbind(policy, 0)
+    }
+    /// Moves physical memory backing this [`Pages`] to `node`, and binds it there, so that pages touched for the first time
+    /// are placed there too. Pages shared with other processes are not moved.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Advise`] if `node` does not exist, or if the kernel does not support NUMA.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x4000);
+    /// memory[0] = 1;
+    /// memory.migrate_to_node(0).unwrap();
+    /// assert_eq!(memory.page_nodes(),vec![Some(0),None,None,None]);
+    /// ```
+    pub fn migrate_to_node(&mut self, node: usize) -> Result<(), PagesError> {
+        self.mbind(&NumaPolicy::Bind(vec![node]), MPOL_MF_MOVE)
+    }
+    fn mbind(&mut self, policy: &NumaPolicy, flags: c_long) -> Result<(), PagesError> {
+        let (mode, mask) = policy.mode_and_mask();
+        let res = unsafe {
+            syscall(
+                syscalls::MBIND,
+                self.ptr,
+                self.len,
+                mode,
+                mask_ptr(&mask),
+                max_node(&mask),
+                flags,
+            )
+        };
+        if res == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Advise));
+        }
+        Ok(())
+    }
+    /// Returns, for each page of this [`Pages`], the NUMA node its physical memory currently lives on, or [`None`] if it
+    /// is not backed by physical memory.
+    /// # Panics
+    /// Panics if the kernel refuses to report placement of the pages(e.g. because it does not support NUMA).
+    #[must_use]
+    pub fn page_nodes(&self) -> Vec<Option<usize>> {
+        self.try_page_nodes().unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::page_nodes`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Query`] if the kernel refuses to report placement of the pages(e.g. because
+    /// it does not support NUMA).
+    pub fn try_page_nodes(&self) -> Result<Vec<Option<usize>>, PagesError> {
+        let page_size = page_size();
+        let pages: Vec<*mut u8> = (0..self.len / page_size)
+            .map(|page| self.ptr.wrapping_add(page * page_size))
+            .collect();
+        let mut status = vec![0 as c_int; pages.len()];
+        let res = unsafe {
+            syscall(
+                syscalls::MOVE_PAGES,
+                0 as c_long,
+                pages.len(),
+                pages.as_ptr(),
+                std::ptr::null::<c_int>(),
+                status.as_mut_ptr(),
+                0 as c_long,
+            )
+        };
+        if res == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Query));
+        }
+        // Pages which are not backed by physical memory have a negative error code instead of a node.
+        Ok(status
+            .into_iter()
+            .map(|node| usize::try_from(node).ok())
+            .collect())
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_numa_policies() {
+        let page_size = page_size();
+        for policy in [
+            NumaPolicy::Default,
+            NumaPolicy::Bind(vec![0]),
+            NumaPolicy::Interleave(vec![0]),
+            NumaPolicy::Preferred(0),
+        ] {
+            let mut pages: Pages<AllowRead, AllowWrite, DenyExec> =
+                Pages::new_numa(4 * page_size, &policy);
+            pages[2 * page_size] = 1;
+            assert_eq!(pages.page_nodes(), vec![None, None, Some(0), None]);
+            assert_eq!(pages.try_page_nodes().unwrap(), pages.page_nodes());
+        }
+    }
+    #[test]
+    fn test_numa_invalid_node() {
+        // Node masks are limited to a page worth of bits.
+        let node = 8 * page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(page_size());
+        let err = pages
+            .set_numa_policy(&NumaPolicy::Bind(vec![node]))
+            .unwrap_err();
+        assert_eq!(err.operation(), PagesOperation::Advise);
+        assert!(pages.migrate_to_node(node).is_err());
+        assert!(NumaPolicy::Interleave(vec![])
+            .apply_to_current_thread()
+            .is_err());
+    }
+    #[test]
+    fn test_numa_paged_vec() {
+        let mut vec: PagedVec<u8> = PagedVec::new_numa(page_size(), &NumaPolicy::Bind(vec![0]));
+        vec.push(1);
+        vec.migrate_to_node(0).unwrap();
+        assert_eq!(vec.page_nodes()[0], Some(0));
+        assert_eq!(vec.try_page_nodes().unwrap()[0], Some(0));
+    }
+}
@@ -0,0 +1,191 @@
+use crate::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+/// A global allocator, which places large allocations directly in memory pages acquired from the kernel, and forwards smaller
+/// ones to [`System`]. This gives ordinary collections, like [`Vec`] or [`std::collections::HashMap`], the speed advantages
+/// of [`Pages`] for very large sizes, without rewriting them as [`PagedVec`]. On unix-like systems, large allocations are
+/// grown and shrunk in place with `mremap` where possible, instead of being copied.
+///
+/// Memory of large allocations is mapped directly, not using [`Pages`], so it is not included in [`stats`], nor recorded
+/// with the `track_mappings` feature.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// #[global_allocator]
+/// static ALLOCATOR:PageAllocator = PageAllocator::with_threshold(0x10_0000);
+/// // Bigger than the threshold, placed directly in pages.
+/// let mut big = vec![0_u8; 0x40_0000];
+/// big[0x3F_FFFF] = 1;
+/// big.resize(0x80_0000, 2);
+/// // Smaller than the threshold, allocated by `System`.
+/// let small = vec![0_u8; 0x100];
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PageAllocator {
+    threshold: usize,
+}
+impl PageAllocator {
+    /// Default threshold, above which allocations are placed directly in pages: 32 MiB.
+    pub const DEFAULT_THRESHOLD: usize = 0x200_0000;
+    /// Creates a new [`PageAllocator`] with threshold of [`Self::DEFAULT_THRESHOLD`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_threshold(Self::DEFAULT_THRESHOLD)
+    }
+    /// Creates a new [`PageAllocator`], which places allocations of at least `threshold` bytes directly in pages.
+    #[must_use]
+    pub const fn with_threshold(threshold: usize) -> Self {
+        Self { threshold }
+    }
+    /// Returns the size in bytes, starting from which allocations are placed directly in pages.
+    #[must_use]
+    pub const fn threshold(&self) -> usize {
+        self.threshold
+    }
+    /// Checks if an allocation with `layout` is, or should be, placed directly in pages. Pages are only aligned to a page
+    /// boundary, so allocations requiring bigger alignment are always forwarded to [`System`].
+    fn is_paged(&self, layout: Layout) -> bool {
+        layout.size() >= self.threshold && layout.align() <= page_size()
+    }
+}
+impl Default for PageAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[cfg(target_family = "unix")]
+unsafe fn map_pages(len: usize) -> *mut u8 {
+    const PROT_READ_WRITE: c_int = 0x3;
+    let ptr = mmap(
+        std::ptr::null_mut(),
+        len,
+        PROT_READ_WRITE,
+        MAP_ANYNOMUS | MAP_PRIVATE,
+        NO_FILE,
+        0,
+    );
+    if ptr as usize == usize::MAX {
+        std::ptr::null_mut()
+    } else {
+        ptr.cast::<u8>()
+    }
+}
+#[cfg(target_family = "unix")]
+unsafe fn unmap_pages(ptr: *mut u8, len: usize) {
+    munmap(ptr.cast::<c_void>(), len);
+}
+#[cfg(target_family = "unix")]
+unsafe fn remap_pages(ptr: *mut u8, old_len: usize, new_len: usize) -> *mut u8 {
+    const MREMAP_MAYMOVE: c_int = 1;
+    let new_ptr = mremap(ptr.cast::<c_void>(), old_len, new_len, MREMAP_MAYMOVE);
+    if new_ptr as usize == usize::MAX {
+        std::ptr::null_mut()
+    } else {
+        new_ptr.cast::<u8>()
+    }
+}
+#[cfg(target_family = "windows")]
+unsafe fn map_pages(len: usize) -> *mut u8 {
+    VirtualAlloc(std::ptr::null_mut(), len, MEM_COMMIT, PAGE_READWRITE).cast::<u8>()
+}
+#[cfg(target_family = "windows")]
+unsafe fn unmap_pages(ptr: *mut u8, _len: usize) {
+    VirtualFree(ptr.cast::<winapi::ctypes::c_void>(), 0, MEM_RELEASE);
+}
+#[cfg(target_family = "windows")]
+unsafe fn remap_pages(ptr: *mut u8, old_len: usize, new_len: usize) -> *mut u8 {
+    // Windows can't move mappings, so the contents have to be copied.
+    let new_ptr = map_pages(new_len);
+    if !new_ptr.is_null() {
+        std::ptr::copy_nonoverlapping(ptr, new_ptr, old_len.min(new_len));
+        unmap_pages(ptr, old_len);
+    }
+    new_ptr
+}
+unsafe impl GlobalAlloc for PageAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if self.is_paged(layout) {
+            map_pages(next_page_boundary(layout.size()))
+        } else {
+            System.alloc(layout)
+        }
+    }
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if self.is_paged(layout) {
+            // Freshly mapped pages are always zeroed by the kernel.
+            map_pages(next_page_boundary(layout.size()))
+        } else {
+            System.alloc_zeroed(layout)
+        }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if self.is_paged(layout) {
+            unmap_pages(ptr, next_page_boundary(layout.size()));
+        } else {
+            System.dealloc(ptr, layout);
+        }
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        match (self.is_paged(layout), self.is_paged(new_layout)) {
+            (false, false) => System.realloc(ptr, layout, new_size),
+            (true, true) => remap_pages(
+                ptr,
+                next_page_boundary(layout.size()),
+                next_page_boundary(new_size),
+            ),
+            // Crossing the threshold moves the allocation between pages and `System`.
+            _ => {
+                let new_ptr = self.alloc(new_layout);
+                if !new_ptr.is_null() {
+                    std::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+                    self.dealloc(ptr, layout);
+                }
+                new_ptr
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_page_allocator() {
+        let page_size = page_size();
+        let allocator = PageAllocator::with_threshold(4 * page_size);
+        unsafe {
+            let layout = Layout::from_size_align(4 * page_size + 1, 8).unwrap();
+            let ptr = allocator.alloc_zeroed(layout);
+            assert_eq!(ptr as usize % page_size, 0);
+            assert_eq!(*ptr.add(4 * page_size), 0);
+            *ptr.add(4 * page_size) = 7;
+            // Grow, while staying in pages.
+            let ptr = allocator.realloc(ptr, layout, 0x100 * page_size);
+            assert_eq!(*ptr.add(4 * page_size), 7);
+            *ptr.add(1) = 3;
+            // Shrink below threshold, moving to `System`.
+            let layout = Layout::from_size_align(0x100 * page_size, 8).unwrap();
+            let ptr = allocator.realloc(ptr, layout, 2);
+            assert_eq!(*ptr.add(1), 3);
+            // And back to pages.
+            let layout = Layout::from_size_align(2, 8).unwrap();
+            let ptr = allocator.realloc(ptr, layout, 8 * page_size);
+            assert_eq!(*ptr.add(1), 3);
+            allocator.dealloc(ptr, Layout::from_size_align(8 * page_size, 8).unwrap());
+        }
+    }
+    #[test]
+    fn test_page_allocator_overaligned() {
+        let page_size = page_size();
+        let allocator = PageAllocator::with_threshold(0);
+        unsafe {
+            let layout = Layout::from_size_align(page_size, 2 * page_size).unwrap();
+            let ptr = allocator.alloc(layout);
+            assert_eq!(ptr as usize % (2 * page_size), 0);
+            allocator.dealloc(ptr, layout);
+        }
+        assert_eq!(
+            PageAllocator::default().threshold(),
+            PageAllocator::DEFAULT_THRESHOLD
+        );
+    }
+}
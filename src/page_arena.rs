@@ -0,0 +1,276 @@
+use crate::*;
+use std::alloc::Layout;
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+/// A value, or slice of values, allocated inside a [`PageArena`], which must be dropped when the arena is reset.
+struct DropEntry {
+    ptr: *mut u8,
+    len: usize,
+    drop: unsafe fn(*mut u8, usize),
+}
+unsafe fn drop_slice<T>(ptr: *mut u8, len: usize) {
+    std::ptr::drop_in_place(std::ptr::slice_from_raw_parts_mut(ptr.cast::<T>(), len));
+}
+/// A region(bump) allocator placing values in memory pages acquired directly from the kernel. Allocation only moves a pointer
+/// forward, and all values are released at once, by [`Self::reset`] or when the arena is dropped. The arena grows by adding new
+/// chunks of pages, so values never move, and references to them stay valid until the arena is reset.
+///
+/// [`Self::reset`] decommits all used pages, so an arena kept around between uses(e.g. requests handled by a server) costs no
+/// physical memory while it is not in use, but its address space is reused without asking the kernel for new pages.
+///
+/// Values are dropped when the arena is reset or dropped, so they may only borrow data living at least as long as the arena,
+/// described by `'a`.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// let mut arena = PageArena::new();
+/// for request in 0..4{
+///     let name = arena.alloc(format!("request {request}"));
+///     let scratch = arena.alloc_slice(&[0_u64; 0x100]);
+///     scratch[0] = request;
+///     name.push('!');
+///     // Drops `name`, and releases all memory used for this request.
+///     arena.reset();
+/// }
+/// ```
+/// Values borrowing data which does not outlive the arena are rejected, since their `Drop` could access it after it is gone.
+/// ```compile_fail
+/// # use memory_pages::*;
+/// struct Peek<'v>(&'v Vec<u8>);
+/// impl Drop for Peek<'_> {
+///     fn drop(&mut self) {
+///         println!("{:?}", self.0);
+///     }
+/// }
+/// let arena = PageArena::new();
+/// {
+///     let v = vec![1, 2, 3, 4];
+///     arena.alloc(Peek(&v));
+/// }
+/// drop(arena);
+/// ```
+pub struct PageArena<'a> {
+    chunks: RefCell<Vec<Pages<AllowRead, AllowWrite, DenyExec>>>,
+    /// Index of the chunk allocations are currently made from.
+    current: Cell<usize>,
+    /// Offset of the first free byte inside the current chunk.
+    offset: Cell<usize>,
+    chunk_size: usize,
+    drops: RefCell<Vec<DropEntry>>,
+    /// Invariant, so that `'a` can't be shortened to let in values borrowing shorter-lived data.
+    borrows: PhantomData<fn(&'a ()) -> &'a ()>,
+}
+impl<'a> PageArena<'a> {
+    /// Default size of a chunk of pages added when the arena runs out of space: 1 MiB.
+    pub const DEFAULT_CHUNK_SIZE: usize = 0x10_0000;
+    /// Creates a new, empty [`PageArena`], growing by chunks of [`Self::DEFAULT_CHUNK_SIZE`] bytes. No pages are allocated until
+    /// the first allocation.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_chunk_size(Self::DEFAULT_CHUNK_SIZE)
+    }
+    /// Creates a new, empty [`PageArena`], growing by chunks of at least `chunk_size` bytes, rounded up to next page
+    /// boundary. Allocations bigger than `chunk_size` get a chunk of their own.
+    #[must_use]
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        Self {
+            chunks: RefCell::new(Vec::new()),
+            current: Cell::new(0),
+            offset: Cell::new(0),
+            chunk_size: chunk_size.max(1),
+            drops: RefCell::new(Vec::new()),
+            borrows: PhantomData,
+        }
+    }
+    /// Moves `value` into the arena, returning a reference to it, valid until the arena is reset.
+    /// # Panics
+    /// Panics if kernel can't/refuses to allocate a new chunk.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let arena = PageArena::new();
+    /// let a = arena.alloc(1_u32);
+    /// let b = arena.alloc(2_u32);
+    /// *a += *b;
+    /// assert_eq!(*a, 3);
+    /// ```
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T: 'a>(&self, value: T) -> &mut T {
+        let ptr = self.bump(Layout::new::<T>()).cast::<T>();
+        unsafe { ptr.write(value) };
+        self.register_drop::<T>(ptr.cast::<u8>(), 1);
+        unsafe { &mut *ptr }
+    }
+    /// Clones all elements of `src` into the arena, returning a reference to the copy, valid until the arena is reset.
+    /// # Panics
+    /// Panics if kernel can't/refuses to allocate a new chunk. If cloning an element panics, elements already cloned are
+    /// leaked.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let arena = PageArena::new();
+    /// let words = arena.alloc_slice(&["Hello", "World"]);
+    /// words[1] = "Arena";
+    /// assert_eq!(words.join(" "), "Hello Arena");
+    /// ```
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice<T: Clone + 'a>(&self, src: &[T]) -> &mut [T] {
+        let layout = Layout::array::<T>(src.len()).expect("capacity overflow");
+        let ptr = self.bump(layout).cast::<T>();
+        for (index, elem) in src.iter().enumerate() {
+            unsafe { ptr.add(index).write(elem.clone()) };
+        }
+        self.register_drop::<T>(ptr.cast::<u8>(), src.len());
+        unsafe { std::slice::from_raw_parts_mut(ptr, src.len()) }
+    }
+    /// Drops all values inside the arena, and decommits all pages used by them. The pages stay reserved and are reused by
+    /// following allocations, but are not backed by physical memory until touched again.
+    pub fn reset(&mut self) {
+        self.drop_all();
+        let current = self.current.replace(0);
+        let offset = self.offset.replace(0);
+        let chunks = self.chunks.get_mut();
+        // No allocations were made if there are no chunks.
+        if let Some((last, used)) = chunks.get_mut(..=current).and_then(<[_]>::split_last_mut) {
+            for chunk in used {
+                let len = chunk.len;
+                chunk.decommit(0, len);
+            }
+            // Values in the partially used page were dropped too, so it can be released as a whole.
+            last.decommit(0, next_page_boundary(offset));
+        }
+    }
+    /// Returns the total size of all chunks of pages owned by this arena, in bytes.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.chunks.borrow().iter().map(|chunk| chunk.len).sum()
+    }
+    /// Reserves space for a value with `layout`, adding a new chunk if none of the remaining ones can fit it.
+    fn bump(&self, layout: Layout) -> *mut u8 {
+        let mut chunks = self.chunks.borrow_mut();
+        let mut index = self.current.get();
+        loop {
+            let Some(chunk) = chunks.get(index) else {
+                // Chunks start at a page boundary, so padding is only needed for values aligned to more than a page.
+                let padding = if layout.align() > page_size() {
+                    layout.align() - 1
+                } else {
+                    0
+                };
+                let len = layout
+                    .size()
+                    .checked_add(padding)
+                    .expect("capacity overflow")
+                    .max(self.chunk_size);
+                chunks.push(Pages::new(len));
+                continue;
+            };
+            let offset = if index == self.current.get() {
+                self.offset.get()
+            } else {
+                0
+            };
+            let start = chunk.ptr.wrapping_add(offset);
+            let padding = start.align_offset(layout.align());
+            let end = offset
+                .checked_add(padding)
+                .and_then(|offset| offset.checked_add(layout.size()));
+            match end {
+                Some(end) if end <= chunk.len => {
+                    // Space left in chunks skipped over is wasted until the arena is reset.
+                    self.current.set(index);
+                    self.offset.set(end);
+                    return start.wrapping_add(padding);
+                }
+                _ => index += 1,
+            }
+        }
+    }
+    fn register_drop<T: 'a>(&self, ptr: *mut u8, len: usize) {
+        if std::mem::needs_drop::<T>() && len != 0 {
+            self.drops.borrow_mut().push(DropEntry {
+                ptr,
+                len,
+                drop: drop_slice::<T>,
+            });
+        }
+    }
+    /// Drops all values, in reverse order of allocation.
+    fn drop_all(&mut self) {
+        // Taken out first, so that a panicking `Drop` can't cause any value to be dropped twice.
+        let drops = std::mem::take(self.drops.get_mut());
+        for entry in drops.into_iter().rev() {
+            unsafe { (entry.drop)(entry.ptr, entry.len) };
+        }
+    }
+}
+impl Default for PageArena<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Drop for PageArena<'_> {
+    fn drop(&mut self) {
+        self.drop_all();
+    }
+}
+impl std::fmt::Debug for PageArena<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("PageArena")
+            .field("chunks", &self.chunks.borrow().len())
+            .field("capacity", &self.capacity())
+            .field("chunk_size", &self.chunk_size)
+            .finish()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+    #[test]
+    fn test_arena_growth() {
+        let page_size = page_size();
+        let mut arena = PageArena::with_chunk_size(page_size);
+        assert_eq!(arena.capacity(), 0);
+        let first = arena.alloc(1_u8) as *mut u8;
+        let word = arena.alloc(2_u64);
+        assert_eq!(*word, 2);
+        assert_eq!(word as *mut u64 as usize % 8, 0);
+        // Does not fit in the first chunk.
+        let big = arena.alloc_slice(&vec![3_u16; page_size]);
+        assert!(big.iter().all(|elem| *elem == 3));
+        assert_eq!(arena.capacity(), 3 * page_size);
+        #[repr(align(0x10000))]
+        struct Overaligned(u8);
+        let overaligned = arena.alloc(Overaligned(4));
+        assert_eq!(overaligned as *mut Overaligned as usize % 0x10000, 0);
+        assert_eq!(overaligned.0, 4);
+        arena.reset();
+        // Memory is reused after reset.
+        assert_eq!(arena.alloc(5_u8) as *mut u8, first);
+        assert!(format!("{arena:?}").contains("chunks: 3"));
+    }
+    #[test]
+    fn test_arena_drop() {
+        let rc = Rc::new(());
+        let mut arena = PageArena::new();
+        arena.alloc(rc.clone());
+        arena.alloc_slice(&[rc.clone(), rc.clone()]);
+        arena.alloc(());
+        assert_eq!(Rc::strong_count(&rc), 4);
+        arena.reset();
+        assert_eq!(Rc::strong_count(&rc), 1);
+        arena.alloc(rc.clone());
+        drop(arena);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn test_arena_decommit() {
+        let mut arena = PageArena::new();
+        arena.alloc_slice(&[1_u8; 0x4000]);
+        assert!(arena.chunks.get_mut()[0].resident_bytes() >= 0x4000);
+        arena.reset();
+        assert_eq!(arena.chunks.get_mut()[0].resident_bytes(), 0);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::*;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+/// A [`Box`]-like type placing a single value on its own, dedicated pages. Unlike a [`Box`], it can be [frozen](Self::freeze),
+/// making the pages read-only, so that any stray write to the value causes a segfault instead of silently corrupting it.
+/// Intended for data which is initialised once, and should never change afterwards, like global configuration or
+/// security-sensitive tables.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// let mut config = PageBox::new([0_u32; 16]);
+/// config[3] = 42;
+/// // From now on, `config` can't be changed.
+/// let config = config.freeze();
+/// assert_eq!(config[3], 42);
+/// ```
+pub struct PageBox<T> {
+    pages: Pages<AllowRead, AllowWrite, DenyExec>,
+    pd: PhantomData<T>,
+}
+/// A [`PageBox`] whose pages are read-only, created by [`PageBox::freeze`]. Can be made writable again using
+/// [`Self::thaw`].
+/// # Beware
+/// Values with interior mutability(e.g. containing a [`std::cell::Cell`] or a [`std::sync::Mutex`]) can still be changed
+/// through a shared reference. Doing so while they are frozen will cause a segfault.
+pub struct FrozenPageBox<T> {
+    pages: Pages<AllowRead, DenyWrite, DenyExec>,
+    pd: PhantomData<T>,
+}
+/// Allocates pages able to hold a `T`.
+fn alloc_for<T>() -> Result<Pages<AllowRead, AllowWrite, DenyExec>, PagesError> {
+    // Pages are only aligned to a page boundary.
+    if std::mem::align_of::<T>() > page_size() {
+        return Err(PagesError::invalid_argument(PagesOperation::Map));
+    }
+    Pages::try_new(std::mem::size_of::<T>().max(1))
+}
+impl<T> PageBox<T> {
+    /// Moves `value` onto newly allocated pages.
+    /// # Panics
+    /// Panics if kernel can't/refuses to allocate requested pages, or if `T` must be aligned to more than a page.
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self::try_new(value).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new`]. On failure, `value` is dropped.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if the allocation fails, or if `T` must be aligned to more than a page.
+    pub fn try_new(value: T) -> Result<Self, PagesError> {
+        let pages = alloc_for::<T>()?;
+        unsafe { pages.ptr.cast::<T>().write(value) };
+        Ok(Self {
+            pages,
+            pd: PhantomData,
+        })
+    }
+    /// Makes the pages holding the value read-only. The value can still be read, but writing to it, even through a raw
+    /// pointer, will cause a segfault.
+    /// # Panics
+    /// Panics if the kernel refuses to change permissions of the pages.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let table = PageBox::new([1_u8, 2, 3]).freeze();
+    /// assert_eq!(*table, [1, 2, 3]);
+    /// ```
+    #[must_use]
+    pub fn freeze(self) -> FrozenPageBox<T> {
+        self.try_freeze().unwrap_or_else(|(_, err)| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::freeze`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    pub fn try_freeze(self) -> Result<FrozenPageBox<T>, (Self, PagesError)> {
+        match self.into_pages().try_deny_write() {
+            Ok(pages) => Ok(FrozenPageBox {
+                pages,
+                pd: PhantomData,
+            }),
+            Err((pages, err)) => Err((
+                Self {
+                    pages,
+                    pd: PhantomData,
+                },
+                err,
+            )),
+        }
+    }
+    /// Moves the value out, releasing the pages.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let boxed = PageBox::new(String::from("Hello"));
+    /// assert_eq!(boxed.into_inner(), "Hello");
+    /// ```
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        let pages = self.into_pages();
+        unsafe { pages.ptr.cast::<T>().read() }
+    }
+    /// Gives the pages back, without dropping the value inside them.
+    fn into_pages(self) -> Pages<AllowRead, AllowWrite, DenyExec> {
+        let this = ManuallyDrop::new(self);
+        // `this` is never dropped, so `pages` is moved out of it exactly once.
+        unsafe { std::ptr::read(&this.pages) }
+    }
+}
+impl<T> FrozenPageBox<T> {
+    /// Makes the pages holding the value writable again, turning it back into a [`PageBox`].
+    /// # Panics
+    /// Panics if the kernel refuses to change permissions of the pages.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let frozen = PageBox::new(1_u64).freeze();
+    /// let mut thawed = frozen.thaw();
+    /// *thawed += 1;
+    /// assert_eq!(*thawed.freeze(), 2);
+    /// ```
+    #[must_use]
+    pub fn thaw(self) -> PageBox<T> {
+        self.try_thaw().unwrap_or_else(|(_, err)| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::thaw`].
+    /// # Errors
+    /// Returns `self` and an error with [`PagesOperation::Protect`] if permissions could not be changed.
+    pub fn try_thaw(self) -> Result<PageBox<T>, (Self, PagesError)> {
+        match self.into_pages().try_allow_write() {
+            Ok(pages) => Ok(PageBox {
+                pages,
+                pd: PhantomData,
+            }),
+            Err((pages, err)) => Err((
+                Self {
+                    pages,
+                    pd: PhantomData,
+                },
+                err,
+            )),
+        }
+    }
+    /// Moves the value out, releasing the pages. The value is copied out first, so the pages are never written to.
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        let pages = self.into_pages();
+        unsafe { pages.ptr.cast::<T>().read() }
+    }
+    /// Gives the pages back, without dropping the value inside them.
+    fn into_pages(self) -> Pages<AllowRead, DenyWrite, DenyExec> {
+        let this = ManuallyDrop::new(self);
+        // `this` is never dropped, so `pages` is moved out of it exactly once.
+        unsafe { std::ptr::read(&this.pages) }
+    }
+}
+impl<T> Deref for PageBox<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.pages.ptr.cast::<T>() }
+    }
+}
+impl<T> DerefMut for PageBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.pages.ptr.cast::<T>() }
+    }
+}
+impl<T> Deref for FrozenPageBox<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.pages.ptr.cast::<T>() }
+    }
+}
+impl<T> Drop for PageBox<T> {
+    fn drop(&mut self) {
+        unsafe { std::ptr::drop_in_place(self.pages.ptr.cast::<T>()) };
+    }
+}
+impl<T> Drop for FrozenPageBox<T> {
+    fn drop(&mut self) {
+        // `Drop` of `T` may write to the value, so it is dropped after being copied out of the read-only pages.
+        if std::mem::needs_drop::<T>() {
+            drop(unsafe { self.pages.ptr.cast::<T>().read() });
+        }
+    }
+}
+impl<T: std::fmt::Debug> std::fmt::Debug for PageBox<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        (**self).fmt(f)
+    }
+}
+impl<T: std::fmt::Debug> std::fmt::Debug for FrozenPageBox<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        (**self).fmt(f)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+    #[test]
+    fn test_page_box() {
+        let mut boxed = PageBox::new(vec![1, 2, 3]);
+        boxed.push(4);
+        let frozen = boxed.freeze();
+        assert_eq!(frozen.len(), 4);
+        assert_eq!(format!("{frozen:?}"), "[1, 2, 3, 4]");
+        let mut thawed = frozen.thaw();
+        thawed.clear();
+        assert!(thawed.freeze().into_inner().is_empty());
+    }
+    #[test]
+    fn test_page_box_drop() {
+        let rc = Rc::new(());
+        let boxed = PageBox::new(rc.clone());
+        assert_eq!(Rc::strong_count(&rc), 2);
+        let frozen = boxed.freeze();
+        assert_eq!(Rc::strong_count(&rc), 2);
+        drop(frozen);
+        assert_eq!(Rc::strong_count(&rc), 1);
+        drop(PageBox::new(rc.clone()));
+        assert_eq!(Rc::strong_count(&rc), 1);
+        let _zst = PageBox::new(()).freeze();
+    }
+    #[test]
+    fn test_page_box_overaligned() {
+        #[derive(Debug)]
+        #[repr(align(0x40000))]
+        struct Overaligned;
+        let err = PageBox::try_new(Overaligned).unwrap_err();
+        assert_eq!(err.operation(), PagesOperation::Map);
+    }
+}
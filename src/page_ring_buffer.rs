@@ -0,0 +1,402 @@
+use crate::*;
+use std::os::fd::AsRawFd;
+use std::sync::Arc;
+/// Maps the same `len` bytes of a shared memory object twice, back to back, returning both mappings as one [`Pages`].
+fn map_twice(len: usize) -> Result<Pages<AllowRead, AllowWrite, DenyExec>, PagesError> {
+    const PROT_READ_WRITE: c_int = 0x3;
+    const MAP_SHARED: c_int = 0x1;
+    let both = len
+        .checked_mul(2)
+        .ok_or_else(|| PagesError::invalid_argument(PagesOperation::Map))?;
+    // Only used to create the shared memory object, never accessed.
+    let object = Pages::<DenyRead, DenyWrite, DenyExec>::try_new_shared(len)?;
+    let fd = object
+        .fd
+        .clone()
+        .expect("Pages created with `try_new_shared` must be backed by a shared memory object");
+    // Reserve space for both mappings first, so that they are guaranteed to be adjacent.
+    let base = unsafe {
+        mmap(
+            std::ptr::null_mut(),
+            both,
+            0,
+            MAP_ANYNOMUS | MAP_PRIVATE | MAP_NORESERVE,
+            NO_FILE,
+            0,
+        )
+    };
+    if base as usize == usize::MAX {
+        return Err(PagesError::last_os_error(PagesOperation::Map));
+    }
+    for half in [0, len] {
+        let res = unsafe {
+            mmap(
+                base.cast::<u8>().wrapping_add(half).cast::<c_void>(),
+                len,
+                PROT_READ_WRITE,
+                MAP_SHARED | MAP_FIXED,
+                fd.as_raw_fd(),
+                0,
+            )
+        };
+        if res as usize == usize::MAX {
+            let err = PagesError::last_os_error(PagesOperation::Map);
+            unsafe { munmap(base, both) };
+            return Err(err);
+        }
+    }
+    // Recorded with the length of both views, since that is what is unmapped when `pages` is dropped.
+    let mut pages = Pages::from_raw_parts(base.cast::<u8>(), both);
+    pages.file = Some(MapMode::Shared);
+    pages.fd = Some(fd);
+    Ok(pages)
+}
+/// State shared by both halves of a split [`PageRingBuffer`].
+struct Ring {
+    /// The buffer, mapped twice.
+    pages: Pages<AllowRead, AllowWrite, DenyExec>,
+    capacity: usize,
+    /// Position of the first readable byte. Positions are counted modulo twice the capacity, to tell a full buffer from an
+    /// empty one.
+    head: AtomicUsize,
+    /// Position of the first writable byte.
+    tail: AtomicUsize,
+}
+impl Ring {
+    fn new(capacity: usize) -> Result<Self, PagesError> {
+        let capacity = next_page_boundary(capacity);
+        if capacity == 0 {
+            return Err(PagesError::invalid_argument(PagesOperation::Map));
+        }
+        Ok(Self {
+            pages: map_twice(capacity)?,
+            capacity,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        })
+    }
+    fn distance(&self, from: usize, to: usize) -> usize {
+        (to + 2 * self.capacity - from) % (2 * self.capacity)
+    }
+    fn advance(&self, position: &AtomicUsize, by: usize) {
+        let new = (position.load(Ordering::Relaxed) + by) % (2 * self.capacity);
+        position.store(new, Ordering::Release);
+    }
+    fn len(&self) -> usize {
+        self.distance(
+            self.head.load(Ordering::Acquire),
+            self.tail.load(Ordering::Acquire),
+        )
+    }
+    /// # Safety
+    /// Must only be called by the producer, which is the only one allowed to write into the buffer.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn write_slice(&self) -> &mut [u8] {
+        let tail = self.tail.load(Ordering::Relaxed);
+        // Acquire ensures the consumer is done reading the bytes it consumed, before they are overwritten.
+        let free = self.capacity - self.distance(self.head.load(Ordering::Acquire), tail);
+        let start = self.pages.ptr.add(tail % self.capacity);
+        std::slice::from_raw_parts_mut(start, free)
+    }
+    /// # Safety
+    /// Must only be called by the consumer.
+    unsafe fn read_slice(&self) -> &[u8] {
+        let head = self.head.load(Ordering::Relaxed);
+        // Acquire ensures all committed bytes are visible.
+        let len = self.distance(head, self.tail.load(Ordering::Acquire));
+        let start = self.pages.ptr.add(head % self.capacity);
+        std::slice::from_raw_parts(start, len)
+    }
+    /// # Safety
+    /// Must only be called by the producer.
+    unsafe fn commit(&self, bytes: usize) {
+        let free = self.capacity - self.len();
+        assert!(
+            bytes <= free,
+            "Can't commit {bytes} bytes, only {free} bytes are free!"
+        );
+        self.advance(&self.tail, bytes);
+    }
+    /// # Safety
+    /// Must only be called by the consumer.
+    unsafe fn consume(&self, bytes: usize) {
+        let len = self.len();
+        assert!(
+            bytes <= len,
+            "Can't consume {bytes} bytes, only {len} bytes are readable!"
+        );
+        self.advance(&self.head, bytes);
+    }
+}
+impl std::fmt::Debug for Ring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("Ring")
+            .field("ptr", &self.pages.ptr)
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+/// A ring buffer of bytes, whose pages are mapped twice, back to back, using a shared memory object(`memfd`). Since bytes past the
+/// end of the buffer are the same as bytes at its beginning, both the readable and the writable part of the buffer are always
+/// a single contiguous slice, even if they wrap around. Data can be written in place(e.g. by a `read` syscall) and read in place
+/// (e.g. by a parser), without ever copying it to stitch the two parts together. Can be split into a [`PageRingProducer`] and a
+/// [`PageRingConsumer`], allowing it to be used by two threads at once.
+///
+/// Both views are one mapping, so the buffer is counted in [`stats`] as a single read-write mapping of twice its capacity,
+/// even though only its capacity is backed by memory.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// let mut ring = PageRingBuffer::new(0x1000);
+/// let capacity = ring.capacity();
+/// // Move the start of the buffer close to its end.
+/// ring.commit(capacity - 2);
+/// ring.consume(capacity - 2);
+/// // Written data wraps around the end of the buffer, but still forms a single slice.
+/// ring.write_slice()[..4].copy_from_slice(b"ring");
+/// ring.commit(4);
+/// assert_eq!(ring.read_slice(), b"ring");
+/// ring.consume(4);
+/// assert!(ring.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct PageRingBuffer {
+    ring: Ring,
+}
+impl PageRingBuffer {
+    /// Creates a new, empty [`PageRingBuffer`] with capacity of at least `capacity` bytes, rounded up to next page boundary if
+    /// necessary.
+    /// # Panics
+    /// Panics when a 0-sized buffer is requested, or if kernel can't/refuses to map the buffer.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        assert_ne!(capacity, 0, "0 - sized allcations are not allowed!");
+        Self::try_new(capacity).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if creating or mapping the shared memory object fails, including 0-sized
+    /// buffers.
+    pub fn try_new(capacity: usize) -> Result<Self, PagesError> {
+        Ok(Self {
+            ring: Ring::new(capacity)?,
+        })
+    }
+    /// Returns the maximal number of bytes the buffer can hold.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity
+    }
+    /// Returns the number of bytes which were committed, but not yet consumed.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+    /// Returns `true` if there are no bytes to read.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns the number of bytes which can be written before the buffer is full.
+    #[must_use]
+    pub fn free_len(&self) -> usize {
+        self.capacity() - self.len()
+    }
+    /// Returns all free space of the buffer, as one contiguous slice. Bytes written into it become readable once they are
+    /// [committed](Self::commit).
+    pub fn write_slice(&mut self) -> &mut [u8] {
+        unsafe { self.ring.write_slice() }
+    }
+    /// Makes first `bytes` of [`Self::write_slice`] readable.
+    /// # Panics
+    /// Panics if `bytes` is larger than [`Self::free_len`].
+    pub fn commit(&mut self, bytes: usize) {
+        unsafe { self.ring.commit(bytes) }
+    }
+    /// Returns all committed bytes, which were not yet consumed, as one contiguous slice.
+    #[must_use]
+    pub fn read_slice(&self) -> &[u8] {
+        unsafe { self.ring.read_slice() }
+    }
+    /// Releases first `bytes` of [`Self::read_slice`], allowing their space to be written again.
+    /// # Panics
+    /// Panics if `bytes` is larger than [`Self::len`].
+    pub fn consume(&mut self, bytes: usize) {
+        unsafe { self.ring.consume(bytes) }
+    }
+    /// Splits the buffer into a [`PageRingProducer`], which writes into it, and a [`PageRingConsumer`], which reads from it.
+    /// Each of them can be sent to a different thread. The buffer is released once both are dropped.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let (mut producer, mut consumer) = PageRingBuffer::new(0x1000).split();
+    /// let writer = std::thread::spawn(move ||{
+    ///     for byte in 0..=255_u8{
+    ///         while producer.free_len() == 0{
+    ///             std::thread::yield_now();
+    ///         }
+    ///         producer.write_slice()[0] = byte;
+    ///         producer.commit(1);
+    ///     }
+    /// });
+    /// let mut received = Vec::new();
+    /// while received.len() < 256{
+    ///     let len = consumer.read_slice().len();
+    ///     received.extend_from_slice(consumer.read_slice());
+    ///     consumer.consume(len);
+    /// }
+    /// writer.join().unwrap();
+    /// assert!(received.iter().copied().eq(0..=255));
+    /// ```
+    #[must_use]
+    pub fn split(self) -> (PageRingProducer, PageRingConsumer) {
+        let ring = Arc::new(self.ring);
+        (
+            PageRingProducer { ring: ring.clone() },
+            PageRingConsumer { ring },
+        )
+    }
+}
+/// The writing half of a [`PageRingBuffer`], created by [`PageRingBuffer::split`].
+#[derive(Debug)]
+pub struct PageRingProducer {
+    ring: Arc<Ring>,
+}
+impl PageRingProducer {
+    /// Returns the maximal number of bytes the buffer can hold.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity
+    }
+    /// Returns the number of bytes which can be written before the buffer is full. The consumer may free more space at any
+    /// time.
+    #[must_use]
+    pub fn free_len(&self) -> usize {
+        self.ring.capacity - self.ring.len()
+    }
+    /// Same as [`PageRingBuffer::write_slice`].
+    pub fn write_slice(&mut self) -> &mut [u8] {
+        unsafe { self.ring.write_slice() }
+    }
+    /// Same as [`PageRingBuffer::commit`]. Committed bytes become visible to the consumer.
+    /// # Panics
+    /// Panics if `bytes` is larger than [`Self::free_len`].
+    pub fn commit(&mut self, bytes: usize) {
+        unsafe { self.ring.commit(bytes) }
+    }
+}
+/// The reading half of a [`PageRingBuffer`], created by [`PageRingBuffer::split`].
+#[derive(Debug)]
+pub struct PageRingConsumer {
+    ring: Arc<Ring>,
+}
+impl PageRingConsumer {
+    /// Returns the maximal number of bytes the buffer can hold.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity
+    }
+    /// Returns the number of bytes which can be read. The producer may commit more bytes at any time.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+    /// Returns `true` if there are no bytes to read.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Same as [`PageRingBuffer::read_slice`].
+    #[must_use]
+    pub fn read_slice(&self) -> &[u8] {
+        unsafe { self.ring.read_slice() }
+    }
+    /// Same as [`PageRingBuffer::consume`]. Consumed space becomes free for the producer.
+    /// # Panics
+    /// Panics if `bytes` is larger than [`Self::len`].
+    pub fn consume(&mut self, bytes: usize) {
+        unsafe { self.ring.consume(bytes) }
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_ring_buffer() {
+        let mut ring = PageRingBuffer::new(1);
+        let capacity = ring.capacity();
+        assert_eq!(capacity, page_size());
+        assert_eq!(ring.write_slice().len(), capacity);
+        ring.write_slice()[capacity - 1] = 1;
+        ring.commit(capacity);
+        assert_eq!(ring.free_len(), 0);
+        assert!(ring.write_slice().is_empty());
+        assert_eq!(ring.read_slice()[capacity - 1], 1);
+        ring.consume(capacity - 1);
+        let write = ring.write_slice();
+        assert_eq!(write.len(), capacity - 1);
+        write.fill(2);
+        ring.commit(capacity - 1);
+        // Readable bytes wrap around the end of the buffer.
+        let read = ring.read_slice();
+        assert_eq!(read.len(), capacity);
+        assert_eq!(read[0], 1);
+        assert!(read[1..].iter().all(|byte| *byte == 2));
+        ring.consume(capacity);
+        assert!(ring.is_empty());
+    }
+    #[test]
+    fn test_ring_buffer_stats() {
+        let ring = PageRingBuffer::new(1);
+        let both = 2 * ring.capacity();
+        let address = ring.ring.pages.ptr as usize;
+        let mapping = live_mappings()
+            .into_iter()
+            .find(|mapping| mapping.address() == address)
+            .unwrap();
+        assert_eq!(mapping.len(), both);
+        assert_eq!(mapping.protection(), "RW-");
+        // Other tests map pages in parallel, so only a lower bound can be checked.
+        assert!(stats().get(true, true, false).bytes() >= both);
+        drop(ring);
+        assert!(live_mappings()
+            .iter()
+            .all(|mapping| mapping.address() != address || mapping.len() != both));
+    }
+    #[test]
+    #[should_panic]
+    fn test_ring_buffer_overcommit() {
+        let mut ring = PageRingBuffer::new(1);
+        ring.commit(ring.capacity() + 1);
+    }
+    #[test]
+    fn test_ring_buffer_threads() {
+        const TOTAL: usize = 0x10_0000;
+        let (mut producer, mut consumer) = PageRingBuffer::new(1).split();
+        let writer = std::thread::spawn(move || {
+            let mut written = 0;
+            while written < TOTAL {
+                let slice = producer.write_slice();
+                let len = slice.len().min(TOTAL - written).min(0x777);
+                for (index, byte) in slice[..len].iter_mut().enumerate() {
+                    *byte = ((written + index) % 251) as u8;
+                }
+                producer.commit(len);
+                written += len;
+            }
+        });
+        let mut read = 0;
+        while read < TOTAL {
+            let slice = consumer.read_slice();
+            for (index, byte) in slice.iter().enumerate() {
+                assert_eq!(*byte, ((read + index) % 251) as u8);
+            }
+            let len = slice.len();
+            consumer.consume(len);
+            read += len;
+        }
+        writer.join().unwrap();
+        assert!(consumer.is_empty());
+    }
+}
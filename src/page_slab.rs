@@ -0,0 +1,416 @@
+use crate::*;
+use std::ops::{Index, IndexMut};
+/// A slot of a [`PageSlab`]. Vacant slots form a free list, linking to the next vacant slot of the same chunk.
+enum Slot<T> {
+    Vacant(Option<usize>),
+    Occupied(T),
+}
+/// A chunk of pages, split into slots.
+struct Chunk {
+    /// [`None`] if the chunk was empty, and its pages were released by [`PageSlab::shrink_to_fit`].
+    pages: Option<Pages<AllowRead, AllowWrite, DenyExec>>,
+    /// Number of slots used since the pages were last committed. Slots past it are uninitialised.
+    initialized: usize,
+    /// First slot of the free list.
+    free: Option<usize>,
+    /// Number of occupied slots.
+    occupied: usize,
+    /// Is the chunk in [`PageSlab::available`].
+    available: bool,
+}
+/// A key identifying a value inside a [`PageSlab`]. Returned by [`PageSlab::insert`]. A key stays valid until its value is
+/// removed, after which it may be reused for another value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SlabKey(usize);
+impl SlabKey {
+    /// Returns the index of the slot this key refers to.
+    #[must_use]
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+/// A slab allocator, storing values of a single type in fixed-size slots carved out of memory pages acquired directly from the
+/// kernel. Values never move, so their addresses are stable until they are removed, and there is no per-value allocation
+/// overhead. Vacant slots are reused before new ones, and the slab grows by adding new chunks of pages when it fills.
+///
+/// Chunks which become completely empty are decommitted, so they use no physical memory until they are filled again. Their
+/// address space can be released too, using [`Self::shrink_to_fit`].
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// struct Node{
+///     value:u64,
+///     next:Option<SlabKey>,
+/// }
+/// let mut nodes = PageSlab::new();
+/// let tail = nodes.insert(Node{value:2, next:None});
+/// let head = nodes.insert(Node{value:1, next:Some(tail)});
+/// let next = nodes[head].next.unwrap();
+/// assert_eq!(nodes[next].value, 2);
+/// nodes.remove(tail);
+/// assert!(nodes.get(tail).is_none());
+/// ```
+pub struct PageSlab<T> {
+    chunks: Vec<Chunk>,
+    /// Chunks with at least one vacant slot. Values are inserted into the last one.
+    available: Vec<usize>,
+    slots_per_chunk: usize,
+    len: usize,
+    pd: PhantomData<T>,
+}
+impl<T> PageSlab<T> {
+    /// Default size of a chunk of pages added when the slab fills: 64 KiB.
+    pub const DEFAULT_CHUNK_SIZE: usize = 0x1_0000;
+    /// Creates a new, empty [`PageSlab`], growing by chunks of [`Self::DEFAULT_CHUNK_SIZE`] bytes. No pages are allocated until
+    /// the first value is inserted.
+    /// # Panics
+    /// Panics if `T` must be aligned to more than a page.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_chunk_size(Self::DEFAULT_CHUNK_SIZE)
+    }
+    /// Creates a new, empty [`PageSlab`], growing by chunks of at least `chunk_size` bytes, rounded up to next page boundary.
+    /// Chunks always have space for at least one value.
+    /// # Panics
+    /// Panics if `T` must be aligned to more than a page.
+    #[must_use]
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        // Chunks start at a page boundary, and slots are placed at multiples of their size.
+        assert!(
+            std::mem::align_of::<Slot<T>>() <= page_size(),
+            "Values in a `PageSlab` can't be aligned to more than a page!"
+        );
+        let slot_size = std::mem::size_of::<Slot<T>>();
+        Self {
+            chunks: Vec::new(),
+            available: Vec::new(),
+            slots_per_chunk: next_page_boundary(chunk_size.max(slot_size)) / slot_size,
+            len: 0,
+            pd: PhantomData,
+        }
+    }
+    /// Inserts `value` into a vacant slot, returning its key.
+    /// # Panics
+    /// Panics if kernel can't/refuses to allocate a new chunk.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut slab = PageSlab::new();
+    /// let key = slab.insert("Hello");
+    /// assert_eq!(slab[key], "Hello");
+    /// ```
+    pub fn insert(&mut self, value: T) -> SlabKey {
+        self.insert_mut(value).0
+    }
+    /// Inserts `value` into a vacant slot, returning its key and a reference to it. The address of the value stays the same
+    /// until it is removed.
+    /// # Panics
+    /// Panics if kernel can't/refuses to allocate a new chunk.
+    pub fn insert_mut(&mut self, value: T) -> (SlabKey, &mut T) {
+        let chunk_index = match self.available.last() {
+            Some(chunk_index) => *chunk_index,
+            None => {
+                self.chunks.push(Chunk {
+                    pages: None,
+                    initialized: 0,
+                    free: None,
+                    occupied: 0,
+                    available: true,
+                });
+                self.available.push(self.chunks.len() - 1);
+                self.chunks.len() - 1
+            }
+        };
+        let chunk_bytes = self.slots_per_chunk * std::mem::size_of::<Slot<T>>();
+        let chunk = &mut self.chunks[chunk_index];
+        let pages = chunk.pages.get_or_insert_with(|| Pages::new(chunk_bytes));
+        let slots = pages.ptr.cast::<Slot<T>>();
+        let slot = match chunk.free {
+            Some(slot) => {
+                let Slot::Vacant(next) = (unsafe { &*slots.add(slot) }) else {
+                    unreachable!("Occupied slot in the free list of a `PageSlab`");
+                };
+                chunk.free = *next;
+                slot
+            }
+            None => {
+                chunk.initialized += 1;
+                chunk.initialized - 1
+            }
+        };
+        let slot_ptr = unsafe { slots.add(slot) };
+        unsafe { slot_ptr.write(Slot::Occupied(value)) };
+        chunk.occupied += 1;
+        if chunk.occupied == self.slots_per_chunk {
+            chunk.available = false;
+            self.available.pop();
+        }
+        self.len += 1;
+        let Slot::Occupied(value) = (unsafe { &mut *slot_ptr }) else {
+            unreachable!()
+        };
+        (SlabKey(chunk_index * self.slots_per_chunk + slot), value)
+    }
+    /// Returns a pointer to the slot `key` refers to, if it was ever initialised.
+    fn slot(&self, key: SlabKey) -> Option<*mut Slot<T>> {
+        let chunk = self.chunks.get(key.0 / self.slots_per_chunk)?;
+        let slot = key.0 % self.slots_per_chunk;
+        if slot >= chunk.initialized {
+            return None;
+        }
+        let pages = chunk.pages.as_ref()?;
+        Some(unsafe { pages.ptr.cast::<Slot<T>>().add(slot) })
+    }
+    /// Returns a reference to the value `key` refers to, or [`None`] if there is no such value.
+    #[must_use]
+    pub fn get(&self, key: SlabKey) -> Option<&T> {
+        match unsafe { &*self.slot(key)? } {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant(_) => None,
+        }
+    }
+    /// Returns a mutable reference to the value `key` refers to, or [`None`] if there is no such value.
+    #[must_use]
+    pub fn get_mut(&mut self, key: SlabKey) -> Option<&mut T> {
+        match unsafe { &mut *self.slot(key)? } {
+            Slot::Occupied(value) => Some(value),
+            Slot::Vacant(_) => None,
+        }
+    }
+    /// Returns `true` if `key` refers to a value inside this slab.
+    #[must_use]
+    pub fn contains(&self, key: SlabKey) -> bool {
+        self.get(key).is_some()
+    }
+    /// Removes the value `key` refers to, and returns it, or returns [`None`] if there is no such value. If this leaves the
+    /// chunk of pages holding the value empty, the chunk is decommitted.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut slab = PageSlab::new();
+    /// let key = slab.insert(1);
+    /// assert_eq!(slab.remove(key), Some(1));
+    /// assert_eq!(slab.remove(key), None);
+    /// ```
+    pub fn remove(&mut self, key: SlabKey) -> Option<T> {
+        let slot_ptr = self.slot(key)?;
+        if let Slot::Vacant(_) = unsafe { &*slot_ptr } {
+            return None;
+        }
+        let chunk_index = key.0 / self.slots_per_chunk;
+        let chunk = &mut self.chunks[chunk_index];
+        let Slot::Occupied(value) = (unsafe { slot_ptr.replace(Slot::Vacant(chunk.free)) }) else {
+            unreachable!()
+        };
+        chunk.free = Some(key.0 % self.slots_per_chunk);
+        chunk.occupied -= 1;
+        self.len -= 1;
+        if !chunk.available {
+            chunk.available = true;
+            self.available.push(chunk_index);
+        }
+        if chunk.occupied == 0 {
+            Self::decommit_chunk(chunk);
+        }
+        Some(value)
+    }
+    /// Removes and drops all values, decommitting all chunks.
+    pub fn clear(&mut self) {
+        self.drop_all();
+        self.available.clear();
+        for (chunk_index, chunk) in self.chunks.iter_mut().enumerate() {
+            Self::decommit_chunk(chunk);
+            self.available.push(chunk_index);
+        }
+    }
+    /// Releases the pages of all empty chunks back to the kernel. Keys of values still inside the slab stay valid.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut slab = PageSlab::with_chunk_size(page_size());
+    /// let keys:Vec<_> = (0..page_size()).map(|index|slab.insert(index)).collect();
+    /// let capacity = slab.capacity();
+    /// for key in &keys[1..]{
+    ///     slab.remove(*key);
+    /// }
+    /// slab.shrink_to_fit();
+    /// assert!(slab.capacity() < capacity);
+    /// assert_eq!(slab[keys[0]], 0);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        for chunk in &mut self.chunks {
+            if chunk.occupied == 0 {
+                chunk.pages = None;
+                chunk.initialized = 0;
+                chunk.free = None;
+            }
+        }
+    }
+    /// Returns the number of values inside this slab.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Returns `true` if there are no values inside this slab.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Returns the number of values this slab can hold without allocating more pages.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.pages.is_some())
+            .count()
+            * self.slots_per_chunk
+    }
+    /// Releases physical memory behind an empty chunk, forgetting all its slots.
+    fn decommit_chunk(chunk: &mut Chunk) {
+        if let Some(pages) = &mut chunk.pages {
+            let len = pages.len;
+            pages.decommit(0, len);
+        }
+        chunk.initialized = 0;
+        chunk.free = None;
+    }
+    /// Drops all values, leaving all chunks empty.
+    fn drop_all(&mut self) {
+        for (chunk_index, chunk) in self.chunks.iter_mut().enumerate() {
+            // Each chunk is marked empty before its values are dropped, so that a panicking `Drop` can't cause any value to
+            // be dropped twice. Values following the panicking one are leaked.
+            let initialized = std::mem::replace(&mut chunk.initialized, 0);
+            chunk.free = None;
+            self.len -= std::mem::replace(&mut chunk.occupied, 0);
+            if !chunk.available {
+                chunk.available = true;
+                self.available.push(chunk_index);
+            }
+            let Some(pages) = &chunk.pages else {
+                continue;
+            };
+            let slots = pages.ptr.cast::<Slot<T>>();
+            for slot in 0..initialized {
+                unsafe { std::ptr::drop_in_place(slots.add(slot)) };
+            }
+        }
+    }
+}
+impl<T> Default for PageSlab<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T> Index<SlabKey> for PageSlab<T> {
+    type Output = T;
+    fn index(&self, key: SlabKey) -> &T {
+        self.get(key).expect("Invalid `SlabKey`")
+    }
+}
+impl<T> IndexMut<SlabKey> for PageSlab<T> {
+    fn index_mut(&mut self, key: SlabKey) -> &mut T {
+        self.get_mut(key).expect("Invalid `SlabKey`")
+    }
+}
+impl<T> Drop for PageSlab<T> {
+    fn drop(&mut self) {
+        self.drop_all();
+    }
+}
+impl<T> std::fmt::Debug for PageSlab<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("PageSlab")
+            .field("len", &self.len)
+            .field("capacity", &self.capacity())
+            .field("slots_per_chunk", &self.slots_per_chunk)
+            .finish()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+    #[test]
+    fn test_slab() {
+        let mut slab = PageSlab::with_chunk_size(1);
+        let per_chunk = page_size() / std::mem::size_of::<Slot<u64>>();
+        let keys: Vec<_> = (0..3 * per_chunk as u64)
+            .map(|value| slab.insert(value))
+            .collect();
+        assert_eq!(slab.len(), 3 * per_chunk);
+        assert_eq!(slab.capacity(), 3 * per_chunk);
+        let address = std::ptr::addr_of!(slab[keys[5]]);
+        assert_eq!(slab.remove(keys[4]), Some(4));
+        assert!(!slab.contains(keys[4]));
+        // Vacant slots are reused, and values never move.
+        assert_eq!(slab.insert(7), keys[4]);
+        assert_eq!(std::ptr::addr_of!(slab[keys[5]]), address);
+        slab[keys[5]] += 1;
+        assert_eq!(slab.get(keys[5]), Some(&6));
+        assert_eq!(slab.get(SlabKey(usize::MAX)), None);
+        assert_eq!(slab.capacity(), 3 * per_chunk);
+        for key in &keys[per_chunk..2 * per_chunk] {
+            slab.remove(*key);
+        }
+        slab.shrink_to_fit();
+        assert_eq!(slab.capacity(), 2 * per_chunk);
+        assert_eq!(slab.len(), 2 * per_chunk);
+        let (key, value) = slab.insert_mut(1);
+        *value = 2;
+        assert_eq!(key.index() / per_chunk, 1);
+        assert_eq!(slab[key], 2);
+        assert_eq!(slab.capacity(), 3 * per_chunk);
+    }
+    #[test]
+    fn test_slab_drop() {
+        let rc = Rc::new(());
+        let mut slab = PageSlab::new();
+        let keys: Vec<_> = (0..0x100).map(|_| slab.insert(rc.clone())).collect();
+        drop(slab.remove(keys[3]));
+        assert_eq!(Rc::strong_count(&rc), 0x100);
+        slab.clear();
+        assert_eq!(Rc::strong_count(&rc), 1);
+        assert!(slab.is_empty());
+        assert!(slab.get(keys[0]).is_none());
+        slab.insert(rc.clone());
+        drop(slab);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn test_slab_decommit() {
+        let mut slab = PageSlab::new();
+        let keys: Vec<_> = (0..0x1000).map(|_| slab.insert([1_u8; 8])).collect();
+        assert!(slab.chunks[0].pages.as_ref().unwrap().resident_bytes() > 0);
+        for key in keys {
+            slab.remove(key);
+        }
+        assert_eq!(slab.chunks[0].pages.as_ref().unwrap().resident_bytes(), 0);
+    }
+    #[test]
+    fn test_slab_panic_safety() {
+        use std::cell::Cell;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        struct Counted<'a>(&'a Cell<usize>, bool);
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+                assert!(!self.1);
+            }
+        }
+        let drops = Cell::new(0);
+        let mut slab = PageSlab::new();
+        slab.insert(Counted(&drops, false));
+        slab.insert(Counted(&drops, true));
+        slab.insert(Counted(&drops, false));
+        let res = catch_unwind(AssertUnwindSafe(|| slab.clear()));
+        assert!(res.is_err());
+        // The value after the panicking one is leaked, but none is dropped twice.
+        assert_eq!(drops.get(), 2);
+        assert!(slab.is_empty());
+        slab.insert(Counted(&drops, false));
+        assert_eq!(slab.len(), 1);
+        drop(slab);
+        assert_eq!(drops.get(), 3);
+    }
+}
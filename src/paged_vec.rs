@@ -1,15 +1,27 @@
 // All functions properly documented, with examples!
-use crate::Pages;
+use crate::{HugePageSize, Pages};
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+use crate::{NumaPolicy, PagesError};
 use std::borrow::{Borrow, BorrowMut};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
 use std::marker::PhantomData;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Bound, Deref, DerefMut, RangeBounds};
 /// A [`Vec`]-like type located in memory pages acquired directly from the kernel. For big lengths a faster to
 /// allocate/deallocate than a normal [`Vec`], but considerably slower for small sizes. Intended to be used for very large data
 /// sets, with a rough estimate of capacity known ahead of time.
 /// # Advantages:
 /// 1. 2-3x times faster than default allocator for big vec sizes (over ~20 MB).
 /// 2. memory is released directly to the kernel as soon as [`PagedVec`] is dropped, which may not always be the case for
-/// standard allocator, leading to decreased memory footprint.
+///    standard allocator, leading to decreased memory footprint.
 // 3. More conservative growth model. Since [`PagedVec`] is intended for very large sizes, it is considerably more conservative with
 // allocating memory(1.5x previous cap instead of 2x for standard [`Vec`].
 /// # Disadvantages
@@ -18,6 +30,14 @@ use std::ops::{Deref, DerefMut};
 /// # Examples
 /// Some examples/documentation for functions of this type are derived from examples for [`Vec`] in rust standard library, to
 /// better highlight the differences and similarities.
+/// ```
+/// # use memory_pages::*;
+/// // Capacity is taken from the size hint of the iterator.
+/// let squares:PagedVec<u64> = (0..0x1000).map(|x| x * x).collect();
+/// assert_eq!(squares[0x10], 0x100);
+/// let odd:Vec<u64> = squares.into_iter().filter(|x| x % 2 == 1).collect();
+/// assert_eq!(odd.len(), 0x800);
+/// ```
 pub struct PagedVec<T: Sized> {
     data: Pages<crate::AllowRead, crate::AllowWrite, crate::DenyExec>,
     len: usize,
@@ -33,7 +53,7 @@ impl<T: Sized> PagedVec<T> {
     /// vec.push_within_capacity(0.0).unwrap();
     /// ```
     pub fn new(capacity: usize) -> Self {
-        let bytes_min = (capacity * std::mem::size_of::<T>()).max(0x1000);
+        let bytes_min = (capacity * std::mem::size_of::<T>()).max(crate::page_size());
         let data = Pages::new(bytes_min);
         Self {
             data,
@@ -41,6 +61,24 @@ impl<T: Sized> PagedVec<T> {
             pd: PhantomData,
         }
     }
+    /// Creates a new [`PagedVec`] backed by huge pages of size `huge_size`, with `capacity` rounded up so that it fills whole huge
+    /// pages. Falls back to normal pages if no huge pages are available, see [`Pages::new_huge`].
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec:PagedVec<f64> = PagedVec::new_huge(1000, HugePageSize::Size2MiB);
+    /// assert_eq!(vec.capacity(),HugePageSize::Size2MiB.bytes() / std::mem::size_of::<f64>());
+    /// vec.push_within_capacity(0.0).unwrap();
+    /// ```
+    pub fn new_huge(capacity: usize, huge_size: HugePageSize) -> Self {
+        let bytes_min = (capacity * std::mem::size_of::<T>()).max(1);
+        let data = Pages::new_huge(bytes_min, huge_size);
+        Self {
+            data,
+            len: 0,
+            pd: PhantomData,
+        }
+    }
     /// An alias for [`Self::new`] provided for compatibility purposes.
     pub fn with_capacity(capacity: usize) -> Self {
         Self::new(capacity)
@@ -57,7 +95,7 @@ impl<T: Sized> PagedVec<T> {
     /// }
     /// // push outside capacity, pushed value returned!
     /// assert_eq!(vec.push_within_capacity(5.6),Err(5.6));
-    #[must_use]
+    #[must_use = "the value is returned back if it did not fit"]
     pub fn push_within_capacity(&mut self, t: T) -> Result<(), T> {
         if self.len * std::mem::size_of::<T>() < self.data.len() {
             let slice = unsafe {
@@ -76,7 +114,7 @@ impl<T: Sized> PagedVec<T> {
     /// contrary, it very often slows allocations down. Before using them, test each usage.
     pub fn advise_use_soon(&mut self, used: usize) {
         if self.len() < used {
-            self.resize(used);
+            self.reallocate(used);
         }
         self.data.advise_use_soon(used);
     }
@@ -94,11 +132,39 @@ impl<T: Sized> PagedVec<T> {
     pub fn advise_use_rnd(&mut self) {
         self.data.advise_use_rnd();
     }
+    /// Advises this [`PagedVec`] to be backed by transparent huge pages, if the kernel supports them.
+    /// # Beware
+    /// Usage hints are part of fine-grain memory access adjustments. It is *NOT* always beneficial to use, in
+    /// contrary, it very often slows allocations down. Before using them, test each usage.
+    pub fn advise_hugepage(&mut self) {
+        self.data.advise_hugepage();
+    }
+    /// Advises this [`PagedVec`] not to be backed by transparent huge pages.
+    /// # Beware
+    /// Usage hints are part of fine-grain memory access adjustments. It is *NOT* always beneficial to use, in
+    /// contrary, it very often slows allocations down. Before using them, test each usage.
+    pub fn advise_nohugepage(&mut self) {
+        self.data.advise_nohugepage();
+    }
+    /// Returns the number of bytes of memory reserved by this [`PagedVec`] which are currently backed by physical memory.
+    /// See [`Pages::resident_bytes`].
+    #[cfg(target_family = "unix")]
+    #[must_use]
+    pub fn resident_bytes(&self) -> usize {
+        self.data.resident_bytes()
+    }
+    /// Fallible version of [`Self::resident_bytes`]. See [`Pages::try_resident_bytes`].
+    /// # Errors
+    /// Returns an error with [`crate::PagesOperation::Query`] if the kernel refuses to report residency of the pages.
+    #[cfg(target_family = "unix")]
+    pub fn try_resident_bytes(&self) -> Result<usize, crate::PagesError> {
+        self.data.try_resident_bytes()
+    }
     fn get_next_cap(cap: usize) -> usize {
         //(cap + cap / 2).max(0x1000)
         cap * 2
     }
-    fn resize(&mut self, next_cap: usize) {
+    fn reallocate(&mut self, next_cap: usize) {
         let bytes_cap = next_cap * std::mem::size_of::<T>();
         self.data.resize(bytes_cap);
         /*
@@ -129,7 +195,7 @@ impl<T: Sized> PagedVec<T> {
         if self.len() + additional <= self.capacity() {
             return;
         };
-        self.resize((self.len() + additional).max(Self::get_next_cap(self.capacity())));
+        self.reallocate((self.len() + additional).max(Self::get_next_cap(self.capacity())));
     }
     /// Reserves the minimum capacity for at least additional more elements to be inserted in the given [`PagedVec<T>`]. Unlike
     /// reserve, this will not deliberately over-allocate to speculatively avoid frequent allocations. After calling
@@ -155,7 +221,7 @@ impl<T: Sized> PagedVec<T> {
         if self.len() + additional < self.capacity() {
             return;
         }
-        self.resize(self.len() + additional);
+        self.reallocate(self.len() + additional);
     }
     /// Removes and returns the element at position `index` within the vector,
     /// shifting all elements after it to the left.
@@ -180,6 +246,11 @@ impl<T: Sized> PagedVec<T> {
     /// assert_eq!(v,slice);
     /// ```
     pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len;
+        assert!(
+            index < len,
+            "removal index (is {index}) should be < len (is {len})"
+        );
         // Taken form std lib.
         let ret;
         unsafe {
@@ -211,7 +282,7 @@ impl<T: Sized> PagedVec<T> {
     /// vec.push(5.6);
     pub fn push(&mut self, t: T) {
         if self.len * std::mem::size_of::<T>() >= self.data.len() {
-            self.resize(Self::get_next_cap(self.capacity()));
+            self.reallocate(Self::get_next_cap(self.capacity()));
         }
         unsafe {
             let end = self.as_mut_ptr().add(self.len);
@@ -283,13 +354,495 @@ impl<T: Sized> PagedVec<T> {
         self.drop_all();
         self.len = 0;
     }
-    /// Works exacly the same as [`Self::clear`] but hints the OS that some of the memory occupied by data inside this 
-    /// [`PagedVec`] is going to be unused, allowing it to be temporarily reclaimed. This allows the memory to be 
+    /// Works exacly the same as [`Self::clear`] but hints the OS that some of the memory occupied by data inside this
+    /// [`PagedVec`] is going to be unused, allowing it to be temporarily reclaimed. This allows the memory to be
     /// reserved, but not backed by physical RAM until next use, reducing RAM usage.
-    pub fn clear_decommit(&mut self){
+    pub fn clear_decommit(&mut self) {
         self.clear();
         self.data.decommit(0, self.data.len());
     }
+    /// Returns a pointer to the first element, valid for the whole capacity.
+    fn buf_ptr(&mut self) -> *mut T {
+        self.data.ptr.cast::<T>()
+    }
+    /// Makes sure the capacity is at least `total` elements, growing at least as much as [`Self::push`] would.
+    fn reserve_total(&mut self, total: usize) {
+        if total > self.capacity() {
+            self.reallocate(total.max(Self::get_next_cap(self.capacity())));
+        }
+    }
+    /// Inserts an element at position `index` within the vector, shifting all elements after it to the right.
+    /// # Panics
+    /// Panics if `index > len`.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    /// vec.insert(1, 4);
+    /// assert_eq!(vec, [1, 4, 2, 3][..]);
+    /// vec.insert(4, 5);
+    /// assert_eq!(vec, [1, 4, 2, 3, 5][..]);
+    /// ```
+    pub fn insert(&mut self, index: usize, element: T) {
+        let len = self.len;
+        assert!(
+            index <= len,
+            "insertion index (is {index}) should be <= len (is {len})"
+        );
+        self.reserve_total(len + 1);
+        unsafe {
+            let ptr = self.buf_ptr().add(index);
+            std::ptr::copy(ptr, ptr.add(1), len - index);
+            std::ptr::write(ptr, element);
+        }
+        self.len += 1;
+    }
+    /// Removes an element from the vector and returns it. The removed element is replaced by the last element of the vector.
+    /// This does not preserve ordering, but is *O*(1).
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&["foo", "bar", "baz", "qux"]);
+    /// assert_eq!(vec.swap_remove(1), "bar");
+    /// assert_eq!(vec, ["foo", "qux", "baz"][..]);
+    /// assert_eq!(vec.swap_remove(0), "foo");
+    /// assert_eq!(vec, ["baz", "qux"][..]);
+    /// ```
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let len = self.len;
+        assert!(
+            index < len,
+            "swap_remove index (is {index}) should be < len (is {len})"
+        );
+        unsafe {
+            let base = self.buf_ptr();
+            let value = std::ptr::read(base.add(index));
+            std::ptr::copy(base.add(len - 1), base.add(index), 1);
+            self.len -= 1;
+            value
+        }
+    }
+    /// Shortens the vector, keeping the first `len` elements and dropping the rest. Has no effect if `len` is greater than the
+    /// vector's current length. The capacity is left unchanged.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[1, 2, 3, 4, 5]);
+    /// vec.truncate(2);
+    /// assert_eq!(vec, [1, 2][..]);
+    /// ```
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let tail =
+            std::ptr::slice_from_raw_parts_mut(unsafe { self.buf_ptr().add(len) }, self.len - len);
+        // Length is set first, so that a panicking `Drop` can't cause any element to be dropped twice.
+        self.len = len;
+        unsafe { std::ptr::drop_in_place(tail) };
+    }
+    /// Resizes the vector in-place so that its length is equal to `new_len`. If `new_len` is greater than the current length,
+    /// the vector is extended by clones of `value`, otherwise it is truncated.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.push("hello");
+    /// vec.resize(3, "world");
+    /// assert_eq!(vec, ["hello", "world", "world"][..]);
+    /// vec.resize(1, "unused");
+    /// assert_eq!(vec, ["hello"][..]);
+    /// ```
+    pub fn resize(&mut self, new_len: usize, value: T)
+    where
+        T: Clone,
+    {
+        if new_len <= self.len {
+            self.truncate(new_len);
+            return;
+        }
+        self.reserve_total(new_len);
+        for _ in self.len + 1..new_len {
+            self.push(value.clone());
+        }
+        // The last element is moved in, instead of being cloned.
+        self.push(value);
+    }
+    /// Resizes the vector in-place so that its length is equal to `new_len`. If `new_len` is greater than the current length,
+    /// the vector is extended by values returned from calling `f`, otherwise it is truncated.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// let mut next = 0;
+    /// vec.resize_with(4, || { next += 1; next });
+    /// assert_eq!(vec, [1, 2, 3, 4][..]);
+    /// ```
+    pub fn resize_with<F: FnMut() -> T>(&mut self, new_len: usize, mut f: F) {
+        if new_len <= self.len {
+            self.truncate(new_len);
+            return;
+        }
+        self.reserve_total(new_len);
+        while self.len < new_len {
+            self.push(f());
+        }
+    }
+    /// Clones and appends all elements of `other` to the vector.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.push(1);
+    /// vec.extend_from_slice(&[2, 3, 4]);
+    /// assert_eq!(vec, [1, 2, 3, 4][..]);
+    /// ```
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.reserve_total(self.len + other.len());
+        for elem in other {
+            self.push(elem.clone());
+        }
+    }
+    /// Retains only the elements for which `f` returns `true`, dropping the rest. Preserves the order of retained elements.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[1, 2, 3, 4]);
+    /// vec.retain(|x| *x % 2 == 0);
+    /// assert_eq!(vec, [2, 4][..]);
+    /// ```
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_mut(|elem| f(elem));
+    }
+    /// Retains only the elements for which `f` returns `true`, passing a mutable reference to each element. Preserves the order
+    /// of retained elements.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[1, 2, 3, 4]);
+    /// vec.retain_mut(|x| if *x <= 3 {
+    ///     *x += 1;
+    ///     true
+    /// } else {
+    ///     false
+    /// });
+    /// assert_eq!(vec, [2, 3, 4][..]);
+    /// ```
+    pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        /// Moves not yet processed elements over the removed ones, even if `f` or `Drop` panics.
+        struct Guard<'a, T> {
+            vec: &'a mut PagedVec<T>,
+            processed: usize,
+            deleted: usize,
+            original_len: usize,
+        }
+        impl<T> Drop for Guard<'_, T> {
+            fn drop(&mut self) {
+                if self.deleted > 0 {
+                    unsafe {
+                        let base = self.vec.buf_ptr();
+                        std::ptr::copy(
+                            base.add(self.processed),
+                            base.add(self.processed - self.deleted),
+                            self.original_len - self.processed,
+                        );
+                    }
+                }
+                self.vec.len = self.original_len - self.deleted;
+            }
+        }
+        let original_len = self.len;
+        // Elements are moved around while being processed, so they must not be accessible if `f` panics.
+        self.len = 0;
+        let mut guard = Guard {
+            vec: self,
+            processed: 0,
+            deleted: 0,
+            original_len,
+        };
+        let base = guard.vec.buf_ptr();
+        while guard.processed < original_len {
+            let current = unsafe { &mut *base.add(guard.processed) };
+            if f(current) {
+                if guard.deleted > 0 {
+                    unsafe {
+                        std::ptr::copy_nonoverlapping(
+                            current,
+                            base.add(guard.processed - guard.deleted),
+                            1,
+                        );
+                    }
+                }
+                guard.processed += 1;
+            } else {
+                // Counted as processed before being dropped, so that it is not moved back if its `Drop` panics.
+                guard.processed += 1;
+                guard.deleted += 1;
+                unsafe { std::ptr::drop_in_place(current) };
+            }
+        }
+    }
+    /// Removes all but the first of consecutive elements in the vector that resolve to the same key.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[10, 20, 21, 30, 20]);
+    /// vec.dedup_by_key(|x| *x / 10);
+    /// assert_eq!(vec, [10, 20, 30, 20][..]);
+    /// ```
+    pub fn dedup_by_key<F: FnMut(&mut T) -> K, K: PartialEq>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+    /// Removes all but the first of consecutive elements in the vector satisfying `same_bucket`. It is passed references to
+    /// two elements, and the first one is removed if it returns `true`.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&["foo", "bar", "Bar", "baz", "bar"]);
+    /// vec.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    /// assert_eq!(vec, ["foo", "bar", "baz", "bar"][..]);
+    /// ```
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same_bucket: F) {
+        /// Moves not yet processed elements over the gap left by removed ones, even if `same_bucket` or `Drop` panics.
+        struct FillGapOnDrop<'a, T> {
+            read: usize,
+            write: usize,
+            original_len: usize,
+            vec: &'a mut PagedVec<T>,
+        }
+        impl<T> Drop for FillGapOnDrop<'_, T> {
+            fn drop(&mut self) {
+                unsafe {
+                    let base = self.vec.buf_ptr();
+                    std::ptr::copy(
+                        base.add(self.read),
+                        base.add(self.write),
+                        self.original_len - self.read,
+                    );
+                }
+                self.vec.len = self.original_len - (self.read - self.write);
+            }
+        }
+        let original_len = self.len;
+        if original_len <= 1 {
+            return;
+        }
+        self.len = 0;
+        let mut gap = FillGapOnDrop {
+            read: 1,
+            write: 1,
+            original_len,
+            vec: self,
+        };
+        let base = gap.vec.buf_ptr();
+        while gap.read < original_len {
+            unsafe {
+                let read = base.add(gap.read);
+                let previous = base.add(gap.write - 1);
+                if same_bucket(&mut *read, &mut *previous) {
+                    gap.read += 1;
+                    std::ptr::drop_in_place(read);
+                } else {
+                    std::ptr::copy(read, base.add(gap.write), 1);
+                    gap.write += 1;
+                    gap.read += 1;
+                }
+            }
+        }
+    }
+    /// Removes all consecutive repeated elements in the vector.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[1, 2, 2, 3, 2]);
+    /// vec.dedup();
+    /// assert_eq!(vec, [1, 2, 3, 2][..]);
+    /// ```
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+    /// Removes the elements in `range` from the vector, returning them as an iterator. Elements not yielded by the iterator
+    /// are dropped when it is dropped. If the iterator is leaked(e.g. using [`std::mem::forget`]), the vector may lose
+    /// elements past the start of the range.
+    /// # Panics
+    /// Panics if the start of the range is greater than its end, or if the end is greater than the length of the vector.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    /// let drained:Vec<_> = vec.drain(1..).collect();
+    /// assert_eq!(vec, [1][..]);
+    /// assert_eq!(drained, [2, 3]);
+    /// // A full range clears the vector.
+    /// vec.drain(..);
+    /// assert!(vec.is_empty());
+    /// ```
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> PagedVecDrain<'_, T> {
+        let len = self.len;
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start
+                .checked_add(1)
+                .expect("attempted to index slice from after maximum usize"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => end
+                .checked_add(1)
+                .expect("attempted to index slice up to maximum usize"),
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => len,
+        };
+        assert!(
+            start <= end,
+            "slice index starts at {start} but ends at {end}"
+        );
+        assert!(
+            end <= len,
+            "range end index {end} out of range for slice of length {len}"
+        );
+        // Drained elements, and the tail, are not accessible until the iterator is dropped.
+        self.len = start;
+        PagedVecDrain {
+            vec: self,
+            next: start,
+            end,
+            tail_start: end,
+            tail_len: len - end,
+        }
+    }
+    /// Replaces the elements in `range` with elements of `replace_with`, returning the removed ones as an iterator.
+    /// `replace_with` does not need to be of the same length as `range`. The replacement happens when the returned iterator
+    /// is dropped, even if it was not fully consumed.
+    /// # Panics
+    /// Panics if the start of the range is greater than its end, or if the end is greater than the length of the vector.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[1, 2, 3, 4]);
+    /// let removed:Vec<_> = vec.splice(1..3, [7, 8, 9]).collect();
+    /// assert_eq!(vec, [1, 7, 8, 9, 4][..]);
+    /// assert_eq!(removed, [2, 3]);
+    /// ```
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> PagedVecSplice<'_, I::IntoIter>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        PagedVecSplice {
+            drain: self.drain(range),
+            replace_with: replace_with.into_iter(),
+        }
+    }
+    /// Splits the vector into two at `at`. Returns a newly allocated vector containing elements `[at, len)`, leaving elements
+    /// `[0, at)` in `self`. The capacity of `self` is left unchanged.
+    /// # Panics
+    /// Panics if `at > len`.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    /// let other = vec.split_off(1);
+    /// assert_eq!(vec, [1][..]);
+    /// assert_eq!(other, [2, 3][..]);
+    /// ```
+    #[must_use = "use `.truncate()` if you don't need the other half"]
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let len = self.len;
+        assert!(
+            at <= len,
+            "`at` split index (is {at}) should be <= len (is {len})"
+        );
+        let other_len = len - at;
+        let mut other = Self::new(other_len);
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.buf_ptr().add(at), other.buf_ptr(), other_len);
+        }
+        self.len = at;
+        other.len = other_len;
+        other
+    }
+    /// Moves all elements of `other` into `self`, leaving `other` empty.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    /// let mut other = PagedVec::new(0x1000);
+    /// other.extend_from_slice(&[4, 5, 6]);
+    /// vec.append(&mut other);
+    /// assert_eq!(vec, [1, 2, 3, 4, 5, 6][..]);
+    /// assert!(other.is_empty());
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        let other_len = other.len;
+        self.reserve_total(self.len + other_len);
+        unsafe {
+            std::ptr::copy_nonoverlapping(other.buf_ptr(), self.buf_ptr().add(self.len), other_len);
+        }
+        other.len = 0;
+        self.len += other_len;
+    }
+    /// Forces the length of the vector to `new_len`, without dropping or initializing any elements.
+    /// # Safety
+    /// `new_len` must be less than or equal to [`Self::capacity`], and elements at `old_len..new_len` must be initialized.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec:PagedVec<u32> = PagedVec::new(0x1000);
+    /// unsafe{
+    ///     vec.as_mut_ptr().write(7);
+    ///     vec.set_len(1);
+    /// }
+    /// assert_eq!(vec, [7][..]);
+    /// ```
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.capacity());
+        self.len = new_len;
+    }
+    /// Moves all elements into a newly allocated [`Vec`], releasing the pages of this [`PagedVec`].
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let vec:PagedVec<u8> = (1..=3).collect();
+    /// assert_eq!(vec.into_vec(), vec![1, 2, 3]);
+    /// ```
+    #[must_use]
+    pub fn into_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len);
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.buf_ptr(), vec.as_mut_ptr(), self.len);
+            vec.set_len(self.len);
+        }
+        // Elements were moved out, so only the pages are released.
+        self.len = 0;
+        vec
+    }
+    /// Gives the pages back, without dropping any elements.
+    fn into_pages(self) -> Pages<crate::AllowRead, crate::AllowWrite, crate::DenyExec> {
+        let this = std::mem::ManuallyDrop::new(self);
+        // `this` is never dropped, so `data` is moved out of it exactly once.
+        unsafe { std::ptr::read(&this.data) }
+    }
     fn drop_all(&mut self) {
         use std::mem::MaybeUninit;
         for i in 0..self.len() {
@@ -300,6 +853,62 @@ impl<T: Sized> PagedVec<T> {
         }
     }
 }
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+impl<T: Sized> PagedVec<T> {
+    /// Creates a new [`PagedVec`] with capacity of at least `capacity`, with physical memory backing it placed according
+    /// to `policy`. The policy also applies to memory added when the vector grows.
+    /// # Panics
+    /// Panics if kernel can't/refuses to allocate requested pages, or if `policy` can't be applied.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec:PagedVec<u64> = PagedVec::new_numa(0x1000, &NumaPolicy::Preferred(0));
+    /// vec.push(1);
+    /// ```
+    #[must_use]
+    pub fn new_numa(capacity: usize, policy: &NumaPolicy) -> Self {
+        let mut vec = Self::new(capacity);
+        vec.set_numa_policy(policy)
+            .unwrap_or_else(|err| panic!("{err}"));
+        vec
+    }
+    /// Sets the policy used to place physical memory backing elements of this [`PagedVec`] which are written for the first
+    /// time from now on. See [`Pages::set_numa_policy`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Advise`](crate::PagesOperation::Advise) if any of the nodes does not exist, or if the kernel does not
+    /// support NUMA.
+    pub fn set_numa_policy(&mut self, policy: &NumaPolicy) -> Result<(), PagesError> {
+        self.data.set_numa_policy(policy)
+    }
+    /// Moves physical memory backing this [`PagedVec`] to `node`. See [`Pages::migrate_to_node`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Advise`](crate::PagesOperation::Advise) if `node` does not exist, or if the kernel does not support NUMA.
+    pub fn migrate_to_node(&mut self, node: usize) -> Result<(), PagesError> {
+        self.data.migrate_to_node(node)
+    }
+    /// Returns, for each page of memory reserved by this [`PagedVec`], the NUMA node its physical memory currently lives
+    /// on. See [`Pages::page_nodes`].
+    /// # Panics
+    /// Panics if the kernel refuses to report placement of the pages(e.g. because it does not support NUMA).
+    #[must_use]
+    pub fn page_nodes(&self) -> Vec<Option<usize>> {
+        self.data.page_nodes()
+    }
+    /// Fallible version of [`Self::page_nodes`]. See [`Pages::try_page_nodes`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Query`](crate::PagesOperation::Query) if the kernel refuses to report
+    /// placement of the pages(e.g. because it does not support NUMA).
+    pub fn try_page_nodes(&self) -> Result<Vec<Option<usize>>, PagesError> {
+        self.data.try_page_nodes()
+    }
+}
 impl<T: Sized> Drop for PagedVec<T> {
     fn drop(&mut self) {
         self.drop_all();
@@ -327,6 +936,7 @@ impl<T: Sized> BorrowMut<[T]> for PagedVec<T> {
     }
 }
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod test {
     use super::*;
     #[test]
@@ -349,11 +959,144 @@ mod test {
     fn test_page_vec_drop() {
         let mut vec: PagedVec<String> = PagedVec::new(0x1000);
         assert!(vec.capacity() == 0x1000);
-        for i in 0..vec.capacity() {
+        for _ in 0..vec.capacity() {
             vec.push_within_capacity("".to_owned())
                 .expect("could not push!");
         }
     }
+    #[test]
+    fn test_page_vec_mutation() {
+        let mut vec: PagedVec<u64> = PagedVec::new(0x10);
+        let capacity = vec.capacity();
+        vec.resize(capacity, 1);
+        // Grows past the initial capacity.
+        vec.insert(0, 0);
+        assert_eq!(vec.len(), capacity + 1);
+        assert_eq!(vec[..2], [0, 1]);
+        vec.truncate(3);
+        vec.extend_from_slice(&[2, 2, 3]);
+        assert_eq!(vec, [0, 1, 1, 2, 2, 3][..]);
+        vec.dedup();
+        assert_eq!(vec, [0, 1, 2, 3][..]);
+        assert_eq!(vec.swap_remove(0), 0);
+        assert_eq!(vec, [3, 1, 2][..]);
+        let mut other = vec.split_off(1);
+        assert_eq!(other, [1, 2][..]);
+        other.append(&mut vec);
+        assert!(vec.is_empty());
+        assert_eq!(other, [1, 2, 3][..]);
+        other.retain(|x| *x != 2);
+        assert_eq!(other, [1, 3][..]);
+        other.resize_with(4, || 4);
+        assert_eq!(other, [1, 3, 4, 4][..]);
+        other.dedup_by_key(|x| *x / 2);
+        assert_eq!(other, [1, 3, 4][..]);
+    }
+    #[test]
+    fn test_page_vec_drain_splice() {
+        let mut vec: PagedVec<String> = PagedVec::new(0x10);
+        for i in 0..6 {
+            vec.push(i.to_string());
+        }
+        let mut drain = vec.drain(1..=3);
+        assert_eq!(drain.next_back().unwrap(), "3");
+        assert_eq!(drain.len(), 2);
+        // Dropping drain drops the remaining elements, and moves the tail back.
+        drop(drain);
+        assert_eq!(vec, ["0", "4", "5"].map(String::from).to_vec());
+        // Iterator with an imprecise size hint.
+        let removed: Vec<_> = vec
+            .splice(..1, (10..20).filter(|i| i % 3 == 0).map(|i| i.to_string()))
+            .collect();
+        assert_eq!(removed, ["0"]);
+        assert_eq!(vec, ["12", "15", "18", "4", "5"].map(String::from).to_vec());
+        vec.splice(1..4, None);
+        assert_eq!(vec, ["12", "5"].map(String::from).to_vec());
+        vec.splice(2.., ["6".to_owned()]);
+        assert_eq!(vec, ["12", "5", "6"].map(String::from).to_vec());
+        // The tail must be moved a lot further than the initial capacity.
+        let capacity = vec.capacity();
+        vec.splice(1..1, (0..capacity).map(|i| i.to_string()));
+        assert_eq!(vec.len(), capacity + 3);
+        assert_eq!(vec[capacity + 1], "5");
+    }
+    #[test]
+    fn test_page_vec_panic_safety() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::rc::Rc;
+        let rc = Rc::new(());
+        let mut vec = PagedVec::new(0x10);
+        vec.resize(8, rc.clone());
+        let mut calls = 0;
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            vec.retain(|_| {
+                calls += 1;
+                assert!(calls != 5);
+                calls % 2 == 0
+            });
+        }));
+        assert!(res.is_err());
+        // Two elements were removed, and none were dropped twice or leaked.
+        assert_eq!(vec.len(), 6);
+        assert_eq!(Rc::strong_count(&rc), 7);
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            vec.dedup_by(|_, _| panic!());
+        }));
+        assert!(res.is_err());
+        assert_eq!(vec.len(), 6);
+        assert_eq!(Rc::strong_count(&rc), 7);
+        vec.drain(1..3);
+        assert_eq!(Rc::strong_count(&rc), 5);
+        vec.clear();
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+    #[test]
+    fn test_page_vec_conversions() {
+        use std::collections::HashSet;
+        use std::rc::Rc;
+        let mut vec: PagedVec<u32> = PagedVec::from(vec![3, 1, 2]);
+        vec.extend(&[4, 5]);
+        vec.extend(6..8);
+        for elem in &mut vec {
+            *elem *= 10;
+        }
+        assert_eq!(vec, &[30, 10, 20, 40, 50, 60, 70][..]);
+        let other = PagedVec::from(&vec[..]);
+        assert_eq!(vec, other);
+        let prefix = PagedVec::from(&[30, 10][..]);
+        assert!(vec > prefix);
+        let set: HashSet<PagedVec<u32>> = [vec.clone(), other].into_iter().collect();
+        assert_eq!(set.len(), 1);
+        // `Borrow<[T]>` requires hashes to match.
+        assert!(set.contains(&[30, 10, 20, 40, 50, 60, 70][..]));
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.len(), 7);
+        assert_eq!(iter.next_back(), Some(70));
+        assert_eq!(iter.next(), Some(30));
+        assert_eq!(iter.clone().collect::<Vec<_>>(), [10, 20, 40, 50, 60]);
+        assert_eq!(
+            format!("{iter:?}"),
+            "PagedVecIntoIter([10, 20, 40, 50, 60])"
+        );
+        // Elements not yielded by the iterator are dropped with it.
+        let rc = Rc::new(());
+        let vec: PagedVec<_> = (0..4).map(|_| rc.clone()).collect();
+        let mut iter = vec.into_iter();
+        drop(iter.next());
+        assert_eq!(Rc::strong_count(&rc), 4);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&rc), 1);
+        let vec = PagedVec::from(vec![rc.clone(), rc.clone()]).into_vec();
+        assert_eq!(Rc::strong_count(&rc), 3);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+    #[test]
+    #[should_panic]
+    fn test_page_vec_insert_out_of_bounds() {
+        let mut vec: PagedVec<u8> = PagedVec::new(0x10);
+        vec.insert(1, 0);
+    }
 }
 use std::fmt::{Debug, Formatter};
 impl<T: Debug> Debug for PagedVec<T> {
@@ -392,3 +1135,359 @@ impl<'a, T> IntoIterator for &'a PagedVec<T> {
         self.iter()
     }
 }
+impl<'a, T> IntoIterator for &'a mut PagedVec<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+impl<T> IntoIterator for PagedVec<T> {
+    type Item = T;
+    type IntoIter = PagedVecIntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        let end = self.len;
+        PagedVecIntoIter {
+            data: self.into_pages(),
+            next: 0,
+            end,
+            pd: PhantomData,
+        }
+    }
+}
+impl<T> FromIterator<T> for PagedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut vec = Self::new(iter.size_hint().0);
+        for elem in iter {
+            vec.push(elem);
+        }
+        vec
+    }
+}
+impl<T> Extend<T> for PagedVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve_total(self.len + iter.size_hint().0);
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+impl<'a, T: Copy + 'a> Extend<&'a T> for PagedVec<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+impl<T> From<Vec<T>> for PagedVec<T> {
+    fn from(mut vec: Vec<T>) -> Self {
+        let mut paged = Self::new(vec.len());
+        unsafe {
+            std::ptr::copy_nonoverlapping(vec.as_ptr(), paged.buf_ptr(), vec.len());
+            paged.len = vec.len();
+            // Elements were moved out, so `vec` must only release its buffer.
+            vec.set_len(0);
+        }
+        paged
+    }
+}
+impl<T: Clone> From<&[T]> for PagedVec<T> {
+    fn from(slice: &[T]) -> Self {
+        let mut vec = Self::new(slice.len());
+        vec.extend_from_slice(slice);
+        vec
+    }
+}
+impl<T> From<PagedVec<T>> for Vec<T> {
+    fn from(vec: PagedVec<T>) -> Self {
+        vec.into_vec()
+    }
+}
+impl<T: Hash> Hash for PagedVec<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Same as a slice, so that it is consistent with `Borrow<[T]>`.
+        Hash::hash(&**self, state);
+    }
+}
+impl<T: PartialEq> PartialEq for PagedVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self[..] == other[..]
+    }
+}
+impl<T: Eq> Eq for PagedVec<T> {}
+impl<T: PartialOrd> PartialOrd for PagedVec<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        PartialOrd::partial_cmp(&**self, &**other)
+    }
+}
+impl<T: Ord> Ord for PagedVec<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Ord::cmp(&**self, &**other)
+    }
+}
+/// An iterator moving elements out of a [`PagedVec`], created by its [`IntoIterator`] implementation. Pages are released once
+/// the iterator is dropped.
+pub struct PagedVecIntoIter<T> {
+    data: Pages<crate::AllowRead, crate::AllowWrite, crate::DenyExec>,
+    /// Index of the next element yielded from the front.
+    next: usize,
+    /// Index past the next element yielded from the back.
+    end: usize,
+    pd: PhantomData<T>,
+}
+impl<T> PagedVecIntoIter<T> {
+    /// Returns the remaining elements as a slice.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let vec:PagedVec<_> = ['a', 'b', 'c'].into_iter().collect();
+    /// let mut iter = vec.into_iter();
+    /// let _ = iter.next().unwrap();
+    /// assert_eq!(iter.as_slice(), &['b', 'c']);
+    /// ```
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe {
+            std::slice::from_raw_parts(
+                self.data.ptr.cast::<T>().add(self.next),
+                self.end - self.next,
+            )
+        }
+    }
+    /// Returns the remaining elements as a mutable slice.
+    #[must_use]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                self.data.ptr.cast::<T>().add(self.next),
+                self.end - self.next,
+            )
+        }
+    }
+}
+impl<T> Iterator for PagedVecIntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        if self.next == self.end {
+            return None;
+        }
+        let elem = unsafe { self.data.ptr.cast::<T>().add(self.next).read() };
+        self.next += 1;
+        Some(elem)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.next;
+        (len, Some(len))
+    }
+}
+impl<T> DoubleEndedIterator for PagedVecIntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.next == self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(unsafe { self.data.ptr.cast::<T>().add(self.end).read() })
+    }
+}
+impl<T> ExactSizeIterator for PagedVecIntoIter<T> {}
+impl<T> FusedIterator for PagedVecIntoIter<T> {}
+impl<T> Drop for PagedVecIntoIter<T> {
+    fn drop(&mut self) {
+        let remaining: *mut [T] = self.as_mut_slice();
+        // Marked as yielded first, so that a panicking `Drop` can't cause any element to be dropped twice.
+        self.next = self.end;
+        unsafe { std::ptr::drop_in_place(remaining) };
+    }
+}
+impl<T: Clone> Clone for PagedVecIntoIter<T> {
+    fn clone(&self) -> Self {
+        PagedVec::from(self.as_slice()).into_iter()
+    }
+}
+impl<T: Debug> Debug for PagedVecIntoIter<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_tuple("PagedVecIntoIter")
+            .field(&self.as_slice())
+            .finish()
+    }
+}
+/// An iterator removing a range of elements from a [`PagedVec`], created by [`PagedVec::drain`].
+pub struct PagedVecDrain<'a, T> {
+    vec: &'a mut PagedVec<T>,
+    /// Index of the next element yielded from the front.
+    next: usize,
+    /// Index past the next element yielded from the back.
+    end: usize,
+    /// Index of the first element past the drained range.
+    tail_start: usize,
+    tail_len: usize,
+}
+impl<T> PagedVecDrain<'_, T> {
+    /// Returns the remaining elements as a slice.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut vec = PagedVec::new(0x1000);
+    /// vec.extend_from_slice(&['a', 'b', 'c']);
+    /// let mut drain = vec.drain(..);
+    /// assert_eq!(drain.as_slice(), &['a', 'b', 'c']);
+    /// let _ = drain.next().unwrap();
+    /// assert_eq!(drain.as_slice(), &['b', 'c']);
+    /// ```
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe {
+            std::slice::from_raw_parts(
+                self.vec.data.ptr.cast::<T>().add(self.next),
+                self.end - self.next,
+            )
+        }
+    }
+    /// Moves elements yielded by `replace_with` into the gap between the end of the vector and the tail, until either of them
+    /// runs out. Returns `true` if the gap was filled.
+    fn fill<I: Iterator<Item = T>>(&mut self, replace_with: &mut I) -> bool {
+        while self.vec.len < self.tail_start {
+            let Some(elem) = replace_with.next() else {
+                return false;
+            };
+            unsafe { self.vec.buf_ptr().add(self.vec.len).write(elem) };
+            self.vec.len += 1;
+        }
+        true
+    }
+    /// Makes the gap before the tail `additional` elements larger.
+    fn move_tail(&mut self, additional: usize) {
+        self.vec
+            .reserve_total(self.tail_start + self.tail_len + additional);
+        let new_tail_start = self.tail_start + additional;
+        unsafe {
+            let base = self.vec.buf_ptr();
+            std::ptr::copy(
+                base.add(self.tail_start),
+                base.add(new_tail_start),
+                self.tail_len,
+            );
+        }
+        self.tail_start = new_tail_start;
+    }
+}
+impl<T> Iterator for PagedVecDrain<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        if self.next == self.end {
+            return None;
+        }
+        let elem = unsafe { self.vec.buf_ptr().add(self.next).read() };
+        self.next += 1;
+        Some(elem)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.next;
+        (len, Some(len))
+    }
+}
+impl<T> DoubleEndedIterator for PagedVecDrain<'_, T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.next == self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(unsafe { self.vec.buf_ptr().add(self.end).read() })
+    }
+}
+impl<T> ExactSizeIterator for PagedVecDrain<'_, T> {}
+impl<T> FusedIterator for PagedVecDrain<'_, T> {}
+impl<T> Drop for PagedVecDrain<'_, T> {
+    fn drop(&mut self) {
+        /// Moves the tail back in place, even if dropping remaining elements panics.
+        struct MoveTail<'r, 'a, T>(&'r mut PagedVecDrain<'a, T>);
+        impl<T> Drop for MoveTail<'_, '_, T> {
+            fn drop(&mut self) {
+                let drain = &mut *self.0;
+                let start = drain.vec.len;
+                if drain.tail_start != start {
+                    unsafe {
+                        let base = drain.vec.buf_ptr();
+                        std::ptr::copy(base.add(drain.tail_start), base.add(start), drain.tail_len);
+                    }
+                }
+                drain.vec.len = start + drain.tail_len;
+            }
+        }
+        let guard = MoveTail(self);
+        let remaining = std::ptr::slice_from_raw_parts_mut(
+            unsafe { guard.0.vec.buf_ptr().add(guard.0.next) },
+            guard.0.end - guard.0.next,
+        );
+        guard.0.next = guard.0.end;
+        unsafe { std::ptr::drop_in_place(remaining) };
+    }
+}
+impl<T: Debug> Debug for PagedVecDrain<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_tuple("PagedVecDrain")
+            .field(&self.as_slice())
+            .finish()
+    }
+}
+/// An iterator replacing a range of elements of a [`PagedVec`], created by [`PagedVec::splice`]. Yields the removed elements.
+pub struct PagedVecSplice<'a, I: Iterator> {
+    drain: PagedVecDrain<'a, I::Item>,
+    replace_with: I,
+}
+impl<I: Iterator> Iterator for PagedVecSplice<'_, I> {
+    type Item = I::Item;
+    fn next(&mut self) -> Option<I::Item> {
+        self.drain.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+impl<I: Iterator> DoubleEndedIterator for PagedVecSplice<'_, I> {
+    fn next_back(&mut self) -> Option<I::Item> {
+        self.drain.next_back()
+    }
+}
+impl<I: Iterator> ExactSizeIterator for PagedVecSplice<'_, I> {}
+impl<I: Iterator> Drop for PagedVecSplice<'_, I> {
+    fn drop(&mut self) {
+        // Taken from std lib.
+        self.drain.by_ref().for_each(drop);
+        if self.drain.tail_len == 0 {
+            for elem in self.replace_with.by_ref() {
+                self.drain.vec.push(elem);
+            }
+            return;
+        }
+        if !self.drain.fill(&mut self.replace_with) {
+            return;
+        }
+        // There may be more elements, so the tail is moved by the lower bound of their count first.
+        let (lower_bound, _) = self.replace_with.size_hint();
+        if lower_bound > 0 {
+            self.drain.move_tail(lower_bound);
+            if !self.drain.fill(&mut self.replace_with) {
+                return;
+            }
+        }
+        // Collect any remaining elements, to know exactly how far to move the tail.
+        let mut collected = self.replace_with.by_ref().collect::<Vec<_>>().into_iter();
+        if collected.len() > 0 {
+            self.drain.move_tail(collected.len());
+            let filled = self.drain.fill(&mut collected);
+            debug_assert!(filled);
+        }
+        // The tail is moved back in place when `drain` is dropped.
+    }
+}
+impl<I: Iterator> Debug for PagedVecSplice<'_, I>
+where
+    I::Item: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_tuple("PagedVecSplice")
+            .field(&self.drain.as_slice())
+            .finish()
+    }
+}
@@ -0,0 +1,122 @@
+use crate::*;
+/// Marks types which can be safely viewed from, and written to raw bytes inside [`Pages`]. See [`Pages::view`] and
+/// [`Pages::as_slice_of`].
+/// # Safety
+/// Implement this trait only for types which:
+/// 1. Are valid for any bit pattern, including all zeroes(e.g. no `bool`, `char`, enums or references).
+/// 2. Have no padding bytes, neither between fields nor at the end(e.g. `#[repr(C)]` structs with fields laid out without
+///    gaps).
+/// 3. Have no interior mutability, and no drop glue.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Header{
+///     magic:u32,
+///     version:u16,
+///     flags:u16,
+/// }
+/// // `Header` is made only of integers, with no gaps between them.
+/// unsafe impl Pod for Header{}
+/// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1000);
+/// memory.view_mut::<Header>(0).unwrap().magic = 0xCAFE;
+/// assert_eq!(memory.view::<Header>(0).unwrap().magic,0xCAFE);
+/// ```
+pub unsafe trait Pod: Copy + 'static {}
+macro_rules! impl_pod {
+    ($($ty:ty),*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
+}
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+/// Returns the number of `T`s fitting in `len` bytes at `ptr`, or [`None`] if `T` is zero sized or `ptr` is not aligned
+/// for it.
+fn slice_len_of<T: Pod>(ptr: *const u8, len: usize) -> Option<usize> {
+    let size = std::mem::size_of::<T>();
+    (size != 0 && ptr.align_offset(std::mem::align_of::<T>()) == 0).then(|| len / size)
+}
+/// Returns `true` if a `T` fits in `len` bytes at `ptr`, starting `offset` bytes in, and is aligned.
+fn fits_at<T: Pod>(ptr: *const u8, len: usize, offset: usize) -> bool {
+    let in_bounds = offset
+        .checked_add(std::mem::size_of::<T>())
+        .is_some_and(|end| end <= len);
+    in_bounds
+        && ptr
+            .wrapping_add(offset)
+            .align_offset(std::mem::align_of::<T>())
+            == 0
+}
+impl<W: WritePremisionMarker, E: ExecPremisionMarker> Pages<AllowRead, W, E> {
+    /// Views the contents of this [`Pages`] as a slice of `T`. Bytes at the end, too few to fit another `T`, are not a
+    /// part of the slice. Returns [`None`] if `T` is zero sized, or if it must be aligned to more than a page.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1000);
+    /// memory.as_mut_slice_of::<u32>().unwrap()[1] = 0x0101_0101;
+    /// assert_eq!(memory.as_slice_of::<u32>().unwrap().len(),0x1000 / 4);
+    /// assert_eq!(memory[4],1);
+    /// ```
+    #[must_use]
+    pub fn as_slice_of<T: Pod>(&self) -> Option<&[T]> {
+        let len = slice_len_of::<T>(self.ptr, self.len)?;
+        Some(unsafe { std::slice::from_raw_parts(self.ptr.cast::<T>(), len) })
+    }
+    /// Views `T` located `offset` bytes into this [`Pages`]. Returns [`None`] if `T` does not fit within this [`Pages`] at
+    /// `offset`, or if `offset` is not aligned for `T`.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1000);
+    /// assert_eq!(memory.view::<u64>(8),Some(&0));
+    /// // Misaligned
+    /// assert_eq!(memory.view::<u64>(4),None);
+    /// // Out of bounds
+    /// assert_eq!(memory.view::<u64>(0x1000),None);
+    /// ```
+    #[must_use]
+    pub fn view<T: Pod>(&self, offset: usize) -> Option<&T> {
+        fits_at::<T>(self.ptr, self.len, offset)
+            .then(|| unsafe { &*self.ptr.wrapping_add(offset).cast::<T>() })
+    }
+}
+impl<E: ExecPremisionMarker> Pages<AllowRead, AllowWrite, E> {
+    /// Mutable version of [`Self::as_slice_of`].
+    #[must_use]
+    pub fn as_mut_slice_of<T: Pod>(&mut self) -> Option<&mut [T]> {
+        let len = slice_len_of::<T>(self.ptr, self.len)?;
+        Some(unsafe { std::slice::from_raw_parts_mut(self.ptr.cast::<T>(), len) })
+    }
+    /// Mutable version of [`Self::view`].
+    #[must_use]
+    pub fn view_mut<T: Pod>(&mut self, offset: usize) -> Option<&mut T> {
+        fits_at::<T>(self.ptr, self.len, offset)
+            .then(|| unsafe { &mut *self.ptr.wrapping_add(offset).cast::<T>() })
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_views() {
+        let page_size = page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(page_size);
+        *pages.view_mut::<u16>(2).unwrap() = u16::from_ne_bytes([1, 2]);
+        assert_eq!(pages.get(..4).unwrap(), &[0, 0, 1, 2]);
+        assert!(pages.view_mut::<u16>(3).is_none());
+        assert!(pages.view::<u32>(page_size - 4).is_some());
+        assert!(pages.view::<u32>(page_size - 3).is_none());
+        assert!(pages.view::<u32>(usize::MAX).is_none());
+        *pages.view_mut::<[u8; 3]>(page_size - 3).unwrap() = [7, 8, 9];
+        let pages = pages.deny_write();
+        assert_eq!(pages.view::<[u8; 3]>(page_size - 3), Some(&[7, 8, 9]));
+        assert_eq!(pages.as_slice_of::<[u8; 3]>().unwrap().len(), page_size / 3);
+        assert_eq!(
+            pages.as_slice_of::<u16>().unwrap()[1],
+            u16::from_ne_bytes([1, 2])
+        );
+        assert!(pages.as_slice_of::<[u8; 0]>().is_none());
+    }
+}
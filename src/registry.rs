@@ -0,0 +1,217 @@
+use crate::*;
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+pub(crate) struct Entry {
+    len: usize,
+    protection: usize,
+    backtrace: Arc<Backtrace>,
+}
+/// All live mappings, by address.
+static REGISTRY: Mutex<BTreeMap<usize, Entry>> = Mutex::new(BTreeMap::new());
+fn with_registry<T>(f: impl FnOnce(&mut BTreeMap<usize, Entry>) -> T) -> T {
+    // Entries are always left consistent, and `Drop` of `Pages` must not panic, so poisoning is ignored.
+    let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+    f(&mut registry)
+}
+pub(crate) fn register(ptr: *mut u8, len: usize, protection: usize) {
+    // Captured before locking, since capturing may allocate, and take a while.
+    let backtrace = Arc::new(Backtrace::force_capture());
+    let entry = Entry {
+        len,
+        protection,
+        backtrace,
+    };
+    with_registry(|registry| registry.insert(ptr as usize, entry));
+}
+pub(crate) fn unregister(ptr: *mut u8) {
+    with_registry(|registry| registry.remove(&(ptr as usize)));
+}
+pub(crate) fn set_len(ptr: *mut u8, len: usize) {
+    with_registry(|registry| {
+        if let Some(entry) = registry.get_mut(&(ptr as usize)) {
+            entry.len = len;
+        }
+    });
+}
+/// Takes the entry of the mapping at `ptr` out, before the mapping is moved. Once the mapping is moved, its old address may
+/// be reused by another mapping at any time, so the entry must be taken out while the address is still owned.
+pub(crate) fn take(ptr: *mut u8) -> Option<Entry> {
+    with_registry(|registry| registry.remove(&(ptr as usize)))
+}
+/// Puts an entry taken out with [`take`] back, at the new address of the mapping.
+pub(crate) fn restore(ptr: *mut u8, len: usize, entry: Option<Entry>) {
+    if let Some(mut entry) = entry {
+        entry.len = len;
+        with_registry(|registry| registry.insert(ptr as usize, entry));
+    }
+}
+pub(crate) fn set_protection(ptr: *mut u8, protection: usize) {
+    with_registry(|registry| {
+        if let Some(entry) = registry.get_mut(&(ptr as usize)) {
+            entry.protection = protection;
+        }
+    });
+}
+/// A live [`Pages`] mapping, recorded when the `track_mappings` feature is enabled. Returned by [`live_mappings`].
+#[derive(Debug, Clone)]
+pub struct LiveMapping {
+    address: usize,
+    len: usize,
+    protection: usize,
+    backtrace: Arc<Backtrace>,
+}
+impl LiveMapping {
+    /// Returns the address of the first byte of the mapping.
+    #[must_use]
+    pub fn address(&self) -> usize {
+        self.address
+    }
+    /// Returns the length of the mapping, not counting guard regions.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Always returns `false`, because 0-sized allocations are not allowed. Provided for consistency with [`Self::len`].
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Returns the current permissions of the mapping, named like `"RW-"` or `"R-X"`.
+    #[must_use]
+    pub fn protection(&self) -> &'static str {
+        stats::protection_name(self.protection)
+    }
+    /// Returns the backtrace of the place the mapping was created at.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+}
+impl std::fmt::Display for LiveMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{} bytes of {} pages at {:#x}, created at:\n{}",
+            self.len,
+            self.protection(),
+            self.address,
+            self.backtrace
+        )
+    }
+}
+/// Returns all [`Pages`] currently alive in this process, ordered by address. This includes [`Pages`] leaked using
+/// [`std::mem::forget`], or owned by leaked collections, like [`PagedVec`]. Only available with the `track_mappings` feature.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x1000);
+/// let address = memory.get_ptr(0) as usize;
+/// let mapping = live_mappings().into_iter().find(|mapping|mapping.address() == address).unwrap();
+/// assert_eq!(mapping.len(),memory.len());
+/// assert_eq!(mapping.protection(),"RW-");
+/// ```
+#[must_use]
+pub fn live_mappings() -> Vec<LiveMapping> {
+    with_registry(|registry| {
+        registry
+            .iter()
+            .map(|(address, entry)| LiveMapping {
+                address: *address,
+                len: entry.len,
+                protection: entry.protection,
+                backtrace: entry.backtrace.clone(),
+            })
+            .collect()
+    })
+}
+/// Describes all [`Pages`] still alive, together with backtraces of where they were created, or returns [`None`] if there are
+/// none. Meant to be called at shutdown, when all [`Pages`] should have already been released, to find the ones which leaked.
+/// Only available with the `track_mappings` feature.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// // At the end of `main`:
+/// if let Some(report) = leak_report(){
+///     eprintln!("{report}");
+/// }
+/// ```
+#[must_use]
+pub fn leak_report() -> Option<String> {
+    let mappings = live_mappings();
+    if mappings.is_empty() {
+        return None;
+    }
+    let bytes: usize = mappings.iter().map(LiveMapping::len).sum();
+    let mut report = format!("{} Pages leaked, {bytes} bytes in total:", mappings.len());
+    for mapping in mappings {
+        report.push_str(&format!("\n{mapping}"));
+    }
+    Some(report)
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    fn find(address: *const u8) -> Option<LiveMapping> {
+        live_mappings()
+            .into_iter()
+            .find(|mapping| mapping.address() == address as usize)
+    }
+    #[test]
+    fn test_registry() {
+        let page_size = page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(page_size);
+        let mapping = find(pages.get_ptr(0)).unwrap();
+        assert_eq!(mapping.len(), page_size);
+        assert_eq!(mapping.protection(), "RW-");
+        pages.resize(0x100 * page_size);
+        let mapping = find(pages.get_ptr(0)).unwrap();
+        assert_eq!(mapping.len(), 0x100 * page_size);
+        let pages = pages.deny_write();
+        assert_eq!(find(pages.get_ptr(0)).unwrap().protection(), "R--");
+        let (head, tail) = pages.split_at_page(0x23 * page_size);
+        assert_eq!(find(head.get_ptr(0)).unwrap().len(), 0x23 * page_size);
+        assert_eq!(find(tail.get_ptr(0)).unwrap().len(), 0xDD * page_size);
+        let address = head.get_ptr(0);
+        drop(head);
+        drop(tail);
+        // Other tests may map new pages at the same address, but not with the same length.
+        assert!(find(address).is_none_or(|mapping| mapping.len() != 0x23 * page_size));
+    }
+    #[test]
+    fn test_leak_report() {
+        let guarded: Pages<AllowRead, AllowWrite, DenyExec> =
+            Pages::new_guarded(page_size(), page_size());
+        // The inaccessible reservation guarded pages are made of is not reported.
+        let reservation = guarded.get_ptr(0).wrapping_sub(page_size());
+        assert!(find(reservation).is_none());
+        let address = guarded.get_ptr(0);
+        std::mem::forget(guarded);
+        let report = leak_report().unwrap();
+        assert!(report.contains(&format!("{:#x}", address as usize)));
+        assert!(find(address)
+            .unwrap()
+            .backtrace()
+            .to_string()
+            .contains("test_leak_report"));
+    }
+    #[test]
+    fn test_registry_remap() {
+        // Odd addresses can't belong to any real mapping, so they stand in for the two sides of a remap.
+        let (old, new) = (0x1001 as *mut u8, 0x3001 as *mut u8);
+        register(old, 1, 3);
+        let entry = take(old);
+        // Another thread maps new pages at the address released by the remap, before its entry is restored.
+        register(old, 2, 1);
+        restore(new, 4, entry);
+        assert_eq!(find(old).unwrap().len(), 2);
+        assert_eq!(find(old).unwrap().protection(), "R--");
+        assert_eq!(find(new).unwrap().len(), 4);
+        assert_eq!(find(new).unwrap().protection(), "RW-");
+        unregister(old);
+        unregister(new);
+        // A failed remap leaves the entry in place.
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(page_size());
+        assert!(pages.try_resize(0).is_err());
+        assert_eq!(find(pages.get_ptr(0)).unwrap().len(), page_size());
+    }
+}
@@ -0,0 +1,350 @@
+use crate::*;
+use std::ops::Range;
+/// [`ReservedPages`] represents a contiguous range of virtual address space reserved from the kernel, without any physical
+/// memory behind it. Sub-ranges of it can be committed, making them usable with permissions set by markers `R`, `W` and `E`, and
+/// later uncommitted, returning the memory behind them to the kernel. Since the whole range is reserved up front, committed
+/// data never moves, which allows large tables to grow in place, and reserved but uncommitted memory does not count towards
+/// memory usage of the process.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// // Reserve 1 GiB of address space, without using any memory.
+/// let mut table:ReservedPages<AllowRead,AllowWrite,DenyExec> = ReservedPages::new(0x4000_0000);
+/// // Make the first page usable.
+/// table.commit(0..page_size()).unwrap();
+/// table.get_mut(0..4).unwrap().copy_from_slice(&[1,2,3,4]);
+/// // Grow the table in place, without moving already committed data.
+/// table.commit(page_size()..3 * page_size()).unwrap();
+/// assert_eq!(table.get(0..4).unwrap(),&[1,2,3,4]);
+/// // Uncommitted memory can't be accessed.
+/// assert!(table.get(0..4 * page_size()).is_none());
+/// ```
+pub struct ReservedPages<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> {
+    ptr: *mut u8,
+    len: usize,
+    /// Sorted, non-overlapping and non-adjacent page-aligned ranges which are currently committed.
+    committed: Vec<Range<usize>>,
+    read: PhantomData<R>,
+    write: PhantomData<W>,
+    exec: PhantomData<E>,
+}
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker>
+    ReservedPages<R, W, E>
+{
+    /// Reserves at least `length` bytes of address space, rounded up to next page boundary if necessary. No part of it is
+    /// committed.
+    /// # Panics
+    /// Panics when a 0-sized reservation is attempted, or if kernel can't/refuses to reserve requested address space.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let reserved:ReservedPages<AllowRead,AllowWrite,DenyExec> = ReservedPages::new(0x1234);
+    /// assert!(reserved.len() >= 0x1234);
+    /// assert_eq!(reserved.len() % page_size(),0);
+    /// ```
+    #[must_use]
+    pub fn new(length: usize) -> Self {
+        assert_ne!(length, 0, "0 - sized reservations are not allowed!");
+        Self::try_new(length).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if the reservation fails, including 0-sized reservations.
+    pub fn try_new(length: usize) -> Result<Self, PagesError> {
+        let len = next_page_boundary(length);
+        #[cfg(target_family = "unix")]
+        let ptr = {
+            let ptr = unsafe {
+                mmap(
+                    std::ptr::null_mut(),
+                    len,
+                    0,
+                    MAP_ANYNOMUS | MAP_PRIVATE | MAP_NORESERVE,
+                    NO_FILE,
+                    0,
+                )
+            };
+            if ptr as usize == usize::MAX {
+                return Err(PagesError::last_os_error(PagesOperation::Map));
+            }
+            ptr.cast::<u8>()
+        };
+        #[cfg(target_family = "windows")]
+        let ptr = {
+            use winapi::um::winnt::MEM_RESERVE;
+            let ptr =
+                unsafe { VirtualAlloc(std::ptr::null_mut(), len, MEM_RESERVE, PAGE_NOACCESS) };
+            if ptr.is_null() {
+                return Err(PagesError::last_os_error(PagesOperation::Map));
+            }
+            ptr.cast::<u8>()
+        };
+        Ok(Self {
+            ptr,
+            len,
+            committed: Vec::new(),
+            read: PhantomData,
+            write: PhantomData,
+            exec: PhantomData,
+        })
+    }
+    /// Returns the length of the whole reserved range, committed or not.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Always returns `false`, because 0-sized reservations are not allowed. Provided for consistency with [`Self::len`].
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Returns the total number of committed bytes.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut reserved:ReservedPages<AllowRead,AllowWrite,DenyExec> = ReservedPages::new(0x10_0000);
+    /// assert_eq!(reserved.committed_len(),0);
+    /// reserved.commit(0..1).unwrap();
+    /// assert_eq!(reserved.committed_len(),page_size());
+    /// ```
+    #[must_use]
+    pub fn committed_len(&self) -> usize {
+        self.committed
+            .iter()
+            .map(|range| range.end - range.start)
+            .sum()
+    }
+    /// Returns `true` if every page in `range` is committed. Empty ranges are always committed.
+    #[must_use]
+    pub fn is_committed(&self, range: Range<usize>) -> bool {
+        if range.start >= range.end {
+            return true;
+        }
+        self.committed
+            .iter()
+            .any(|committed| committed.start <= range.start && range.end <= committed.end)
+    }
+    /// Rounds `range` outwards, to the pages it touches, checking it lies within the reservation.
+    fn page_range(
+        &self,
+        range: Range<usize>,
+        operation: PagesOperation,
+    ) -> Result<Range<usize>, PagesError> {
+        if range.start > range.end || range.end > self.len {
+            return Err(PagesError::invalid_argument(operation));
+        }
+        Ok(prev_boundary(range.start, page_size())..next_page_boundary(range.end))
+    }
+    /// Commits all pages touched by `range`, making them accessible with permissions described by `R`, `W` and `E`. Newly
+    /// committed pages are filled with zeroes, already committed pages are left unchanged.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Commit`] if `range` does not lie within the reservation, or if the kernel
+    /// can't/refuses to commit the pages.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut reserved:ReservedPages<AllowRead,AllowWrite,DenyExec> = ReservedPages::new(0x10_0000);
+    /// reserved.commit(10..20).unwrap();
+    /// // Whole page containing `10..20` is committed.
+    /// assert!(reserved.is_committed(0..page_size()));
+    /// assert_eq!(reserved.get(0..page_size()).unwrap()[15],0);
+    /// // Committing outside of the reservation fails.
+    /// assert!(reserved.commit(0..0x20_0000).is_err());
+    /// ```
+    pub fn commit(&mut self, range: Range<usize>) -> Result<(), PagesError> {
+        let range = self.page_range(range, PagesOperation::Commit)?;
+        if range.is_empty() {
+            return Ok(());
+        }
+        let ptr = self.ptr.wrapping_add(range.start);
+        let len = range.end - range.start;
+        #[cfg(target_family = "unix")]
+        if unsafe { mprotect(ptr.cast::<c_void>(), len, Pages::<R, W, E>::bitmask()) } == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Commit));
+        }
+        #[cfg(target_family = "windows")]
+        if unsafe {
+            VirtualAlloc(
+                ptr.cast::<winapi::ctypes::c_void>(),
+                len,
+                MEM_COMMIT,
+                Pages::<R, W, E>::flProtect(),
+            )
+        }
+        .is_null()
+        {
+            return Err(PagesError::last_os_error(PagesOperation::Commit));
+        }
+        self.mark_committed(range);
+        Ok(())
+    }
+    /// Uncommits all pages lying entirely within `range`, releasing the memory behind them to the kernel and making them
+    /// inaccessible. Contents of those pages are lost. Pages only partially covered by `range` are left committed, so that
+    /// data outside `range` is never lost.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Uncommit`] if `range` does not lie within the reservation, or if the kernel
+    /// can't/refuses to uncommit the pages.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut reserved:ReservedPages<AllowRead,AllowWrite,DenyExec> = ReservedPages::new(0x10_0000);
+    /// reserved.commit(0..2 * page_size()).unwrap();
+    /// reserved.uncommit(page_size()..2 * page_size()).unwrap();
+    /// assert!(reserved.is_committed(0..page_size()));
+    /// assert!(!reserved.is_committed(page_size()..2 * page_size()));
+    /// ```
+    pub fn uncommit(&mut self, range: Range<usize>) -> Result<(), PagesError> {
+        self.page_range(range.clone(), PagesOperation::Uncommit)?;
+        let range = next_page_boundary(range.start)..prev_boundary(range.end, page_size());
+        if range.start >= range.end {
+            return Ok(());
+        }
+        let ptr = self.ptr.wrapping_add(range.start);
+        let len = range.end - range.start;
+        #[cfg(target_family = "unix")]
+        {
+            // Mapping fresh, inaccessible memory over the range releases both memory and commit charge of old pages.
+            let res = unsafe {
+                mmap(
+                    ptr.cast::<c_void>(),
+                    len,
+                    0,
+                    MAP_ANYNOMUS | MAP_PRIVATE | MAP_NORESERVE | MAP_FIXED,
+                    NO_FILE,
+                    0,
+                )
+            };
+            if res as usize == usize::MAX {
+                return Err(PagesError::last_os_error(PagesOperation::Uncommit));
+            }
+        }
+        #[cfg(target_family = "windows")]
+        {
+            use winapi::um::winnt::MEM_DECOMMIT;
+            if unsafe { VirtualFree(ptr.cast::<winapi::ctypes::c_void>(), len, MEM_DECOMMIT) } == 0
+            {
+                return Err(PagesError::last_os_error(PagesOperation::Uncommit));
+            }
+        }
+        self.mark_uncommitted(range);
+        Ok(())
+    }
+    fn mark_committed(&mut self, mut range: Range<usize>) {
+        // Merge all overlapping or adjacent ranges into `range`.
+        self.committed.retain(|committed| {
+            if committed.end < range.start || range.end < committed.start {
+                return true;
+            }
+            range.start = range.start.min(committed.start);
+            range.end = range.end.max(committed.end);
+            false
+        });
+        let index = self
+            .committed
+            .partition_point(|committed| committed.start < range.start);
+        self.committed.insert(index, range);
+    }
+    fn mark_uncommitted(&mut self, range: Range<usize>) {
+        let mut remaining = Vec::with_capacity(self.committed.len() + 1);
+        for committed in self.committed.drain(..) {
+            if committed.start < range.start {
+                remaining.push(committed.start..committed.end.min(range.start));
+            }
+            if range.end < committed.end {
+                remaining.push(committed.start.max(range.end)..committed.end);
+            }
+        }
+        self.committed = remaining;
+    }
+}
+impl<W: WritePremisionMarker, E: ExecPremisionMarker> ReservedPages<AllowRead, W, E> {
+    /// Returns the data inside `range`, or [`None`] if any part of it is not committed.
+    #[must_use]
+    pub fn get(&self, range: Range<usize>) -> Option<&[u8]> {
+        if range.start > range.end || !self.is_committed(range.clone()) {
+            return None;
+        }
+        Some(unsafe {
+            std::slice::from_raw_parts(self.ptr.add(range.start), range.end - range.start)
+        })
+    }
+}
+impl<E: ExecPremisionMarker> ReservedPages<AllowRead, AllowWrite, E> {
+    /// Returns the data inside `range` mutably, or [`None`] if any part of it is not committed.
+    #[must_use]
+    pub fn get_mut(&mut self, range: Range<usize>) -> Option<&mut [u8]> {
+        if range.start > range.end || !self.is_committed(range.clone()) {
+            return None;
+        }
+        Some(unsafe {
+            std::slice::from_raw_parts_mut(self.ptr.add(range.start), range.end - range.start)
+        })
+    }
+}
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> std::fmt::Debug
+    for ReservedPages<R, W, E>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("ReservedPages")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .field("committed", &self.committed)
+            .finish()
+    }
+}
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Drop
+    for ReservedPages<R, W, E>
+{
+    fn drop(&mut self) {
+        #[cfg(target_family = "unix")]
+        unsafe {
+            munmap(self.ptr.cast::<c_void>(), self.len);
+        }
+        #[cfg(target_family = "windows")]
+        unsafe {
+            VirtualFree(self.ptr.cast::<winapi::ctypes::c_void>(), 0, MEM_RELEASE);
+        }
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_reserve_commit() {
+        let page_size = page_size();
+        let mut reserved: ReservedPages<AllowRead, AllowWrite, DenyExec> =
+            ReservedPages::new(0x10_0000 * page_size);
+        assert_eq!(reserved.committed_len(), 0);
+        reserved.commit(0..page_size).unwrap();
+        reserved.commit(2 * page_size..3 * page_size).unwrap();
+        assert_eq!(
+            reserved.committed,
+            vec![0..page_size, 2 * page_size..3 * page_size]
+        );
+        reserved.commit(page_size..page_size + 1).unwrap();
+        assert_eq!(reserved.committed, vec![0..3 * page_size]);
+        reserved.get_mut(0..3 * page_size).unwrap().fill(7);
+        reserved.uncommit(page_size..2 * page_size).unwrap();
+        assert_eq!(
+            reserved.committed,
+            vec![0..page_size, 2 * page_size..3 * page_size]
+        );
+        assert!(reserved.get(0..3 * page_size).is_none());
+        assert_eq!(reserved.get(2 * page_size..3 * page_size).unwrap()[0], 7);
+        // Uncommitted pages are zeroed once committed again.
+        reserved.commit(page_size..2 * page_size).unwrap();
+        assert_eq!(reserved.get(page_size..page_size + 1).unwrap(), &[0]);
+    }
+    #[test]
+    fn test_uncommit_partial_page() {
+        let page_size = page_size();
+        let mut reserved: ReservedPages<AllowRead, AllowWrite, DenyExec> =
+            ReservedPages::new(2 * page_size);
+        reserved.commit(0..2 * page_size).unwrap();
+        reserved.get_mut(0..1).unwrap()[0] = 3;
+        reserved.uncommit(1..2 * page_size).unwrap();
+        assert_eq!(reserved.committed, vec![0..page_size]);
+        assert_eq!(reserved.get(0..1).unwrap(), &[3]);
+        assert!(reserved.uncommit(0..3 * page_size).is_err());
+    }
+}
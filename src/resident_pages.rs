@@ -0,0 +1,125 @@
+use crate::*;
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Returns, for each page of this [`Pages`], whether it is currently backed by physical memory. Pages which were
+    /// never touched, or were released with [`Self::decommit`], or swapped out, are not resident. The result is only a
+    /// snapshot: the kernel may change it at any time.
+    /// # Panics
+    /// Panics if the kernel refuses to report residency of the pages(should never happen).
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(4 * page_size());
+    /// memory[page_size()] = 1;
+    /// let residency = memory.residency();
+    /// assert_eq!(residency.len(),4);
+    /// assert!(residency[1]);
+    /// ```
+    #[must_use]
+    pub fn residency(&self) -> Vec<bool> {
+        self.try_residency().unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::residency`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Query`] if the kernel refuses to report residency of the pages.
+    pub fn try_residency(&self) -> Result<Vec<bool>, PagesError> {
+        self.page_residency(0, self.len)
+    }
+    /// Returns the number of bytes of this [`Pages`] which are currently backed by physical memory.
+    /// # Panics
+    /// Panics if the kernel refuses to report residency of the pages(should never happen).
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(4 * page_size());
+    /// memory[0] = 1;
+    /// assert!(memory.resident_bytes() >= page_size());
+    /// memory.decommit(0, memory.len());
+    /// # #[cfg(target_os = "linux")]
+    /// assert_eq!(memory.resident_bytes(),0);
+    /// ```
+    #[must_use]
+    pub fn resident_bytes(&self) -> usize {
+        self.try_resident_bytes()
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::resident_bytes`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Query`] if the kernel refuses to report residency of the pages.
+    pub fn try_resident_bytes(&self) -> Result<usize, PagesError> {
+        let resident = self
+            .try_residency()?
+            .into_iter()
+            .filter(|page| *page)
+            .count();
+        Ok(resident * page_size())
+    }
+    /// Returns `true` if the page containing byte at `offset` is currently backed by physical memory.
+    /// # Panics
+    /// Panics if `offset` is not less than the length of this [`Pages`], or if the kernel refuses to report residency of the
+    /// page(should never happen).
+    #[must_use]
+    pub fn is_resident(&self, offset: usize) -> bool {
+        assert!(
+            offset < self.len,
+            "Offset {offset} is out of bounds of Pages of length {}!",
+            self.len
+        );
+        self.page_residency(prev_boundary(offset, page_size()), 1)
+            .unwrap_or_else(|err| panic!("{err}"))[0]
+    }
+    /// Queries residency of pages in range `[beginning, beginning + length)`, `beginning` being page aligned.
+    fn page_residency(&self, beginning: usize, length: usize) -> Result<Vec<bool>, PagesError> {
+        let mut residency = vec![0_u8; length.div_ceil(page_size())];
+        let res = unsafe {
+            mincore(
+                self.ptr.wrapping_add(beginning).cast::<c_void>(),
+                length,
+                residency.as_mut_ptr(),
+            )
+        };
+        if res == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Query));
+        }
+        // Only the lowest bit describes residency, the rest is reserved.
+        Ok(residency.into_iter().map(|page| page & 1 != 0).collect())
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test_residency() {
+        let page_size = page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(4 * page_size);
+        assert_eq!(pages.residency(), vec![false; 4]);
+        assert_eq!(pages.resident_bytes(), 0);
+        pages[0] = 1;
+        pages[2 * page_size + 5] = 1;
+        assert_eq!(pages.residency(), vec![true, false, true, false]);
+        assert_eq!(pages.resident_bytes(), 2 * page_size);
+        assert_eq!(pages.try_residency().unwrap(), pages.residency());
+        assert_eq!(pages.try_resident_bytes().unwrap(), 2 * page_size);
+        assert!(pages.is_resident(2 * page_size));
+        assert!(!pages.is_resident(page_size + 7));
+        pages.decommit(2 * page_size, page_size);
+        #[cfg(target_os = "linux")]
+        assert_eq!(pages.residency(), vec![true, false, false, false]);
+    }
+    #[test]
+    #[should_panic]
+    fn test_is_resident_out_of_bounds() {
+        let pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(page_size());
+        let _ = pages.is_resident(page_size());
+    }
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_paged_vec_clear_decommit() {
+        let mut vec: PagedVec<u64> = PagedVec::new(0x10000);
+        for i in 0..0x10000 {
+            vec.push(i);
+        }
+        assert!(vec.resident_bytes() >= 0x10000 * std::mem::size_of::<u64>());
+        vec.clear_decommit();
+        assert_eq!(vec.try_resident_bytes().unwrap(), 0);
+    }
+}
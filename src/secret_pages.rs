@@ -0,0 +1,189 @@
+use crate::*;
+use std::ops::{Deref, DerefMut};
+/// [`SecretPages`] is readable and writable memory meant for storing secrets, like cryptographic keys. Compared to plain
+/// [`Pages`], it:
+/// 1. Is locked in physical memory, so it is never written to swap.
+/// 2. Is excluded from core dumps, and is not inherited by child processes created with `fork`.
+/// 3. Is zeroed, using volatile writes which can't be optimized away, before being released.
+/// 4. Never prints its contents in its [`Debug`](std::fmt::Debug) representation.
+///
+/// On linux, it is backed by `memfd_secret` if the kernel supports it, which additionally removes the pages from the
+/// kernel's own direct map, making them inaccessible even to the kernel. Otherwise, it falls back to `mlock` and
+/// `MADV_DONTDUMP`. On other systems, it is only locked, and zeroed on drop.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// let mut key = match SecretPages::try_new(32){
+///     Ok(key)=>key,
+///     // Secret memory is locked, and locking is limited.
+///     Err(err) if err.is_lock_limit_exceeded()=>return,
+///     Err(err)=>panic!("{err}"),
+/// };
+/// key[..4].copy_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF]);
+/// // The contents(`[222, 173, 190, 239, ...]`) are never printed.
+/// assert!(!format!("{key:?}").contains("222"));
+/// ```
+pub struct SecretPages {
+    pages: Pages<AllowRead, AllowWrite, DenyExec>,
+    secret_memory: bool,
+}
+impl SecretPages {
+    /// Allocates new [`SecretPages`] of size at least `length`, rounded up to next page boundary if necessary.
+    /// # Panics
+    /// Panics when a 0-sized allocation is attempted, if kernel can't/refuses to allocate requested pages, or if they can't
+    /// be locked.
+    #[must_use]
+    pub fn new(length: usize) -> Self {
+        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+        Self::try_new(length).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new`].
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if the allocation fails, including 0-sized allocations, with
+    /// [`PagesOperation::Lock`] if the pages could not be locked, or with [`PagesOperation::Advise`] if they could not be
+    /// excluded from core dumps and `fork`.
+    pub fn try_new(length: usize) -> Result<Self, PagesError> {
+        let len = next_page_boundary(length);
+        if len == 0 {
+            return Err(PagesError::invalid_argument(PagesOperation::Map));
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(pages) = Self::map_secret_memory(len) {
+            let mut secret = Self {
+                pages,
+                secret_memory: true,
+            };
+            secret.exclude_from_fork()?;
+            return Ok(secret);
+        }
+        let mut secret = Self {
+            pages: Pages::try_new_locked(len)?,
+            secret_memory: false,
+        };
+        secret.exclude_from_dumps()?;
+        secret.exclude_from_fork()?;
+        Ok(secret)
+    }
+    /// Maps `len` bytes of memory from `memfd_secret`, or returns [`None`] if it is not supported.
+    #[cfg(target_os = "linux")]
+    fn map_secret_memory(len: usize) -> Option<Pages<AllowRead, AllowWrite, DenyExec>> {
+        use std::os::fd::FromRawFd;
+        const SYS_MEMFD_SECRET: std::ffi::c_long = 447;
+        const O_CLOEXEC: std::ffi::c_uint = 0o2000000;
+        let fd = unsafe { syscall(SYS_MEMFD_SECRET, O_CLOEXEC) };
+        if fd < 0 {
+            return None;
+        }
+        // The mapping keeps the memory alive on its own, so the descriptor is closed right away, and the secret can't be
+        // mapped again through it.
+        let file = unsafe { std::fs::File::from_raw_fd(fd as c_int) };
+        file.set_len(len as u64).ok()?;
+        // The descriptor is closed right after mapping, so nothing else can resize or change the memory.
+        unsafe { Pages::map_file(&file, 0, len, MapMode::Shared) }.ok()
+    }
+    #[cfg(target_os = "linux")]
+    fn exclude_from_dumps(&mut self) -> Result<(), PagesError> {
+        const MADV_DONTDUMP: c_int = 16;
+        self.advise(MADV_DONTDUMP)
+    }
+    #[cfg(target_os = "linux")]
+    fn exclude_from_fork(&mut self) -> Result<(), PagesError> {
+        const MADV_DONTFORK: c_int = 10;
+        self.advise(MADV_DONTFORK)
+    }
+    #[cfg(target_os = "linux")]
+    fn advise(&mut self, advice: c_int) -> Result<(), PagesError> {
+        if unsafe { madvise(self.pages.ptr as *mut c_void, self.pages.len, advice) } == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Advise));
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    fn exclude_from_dumps(&mut self) -> Result<(), PagesError> {
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    fn exclude_from_fork(&mut self) -> Result<(), PagesError> {
+        Ok(())
+    }
+    /// Returns `true` if this [`SecretPages`] is backed by `memfd_secret`, and `false` if it uses the `mlock` fallback.
+    #[must_use]
+    pub fn is_secret_memory(&self) -> bool {
+        self.secret_memory
+    }
+    /// Returns the length of this [`SecretPages`].
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+    /// Always returns `false`, because 0-sized allocations are not allowed. Provided for consistency with [`Self::len`].
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+    /// Overwrites the whole memory with zeroes, in a way the compiler can't optimize away.
+    fn wipe(&mut self) {
+        for byte in self.pages.iter_mut() {
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+        std::sync::atomic::compiler_fence(Ordering::SeqCst);
+    }
+}
+impl Deref for SecretPages {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.pages
+    }
+}
+impl DerefMut for SecretPages {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.pages
+    }
+}
+impl Drop for SecretPages {
+    fn drop(&mut self) {
+        // The pages are unlocked and unmapped only after they are wiped, when `self.pages` is dropped.
+        self.wipe();
+    }
+}
+impl std::fmt::Debug for SecretPages {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("SecretPages")
+            .field("len", &self.len())
+            .field("secret_memory", &self.secret_memory)
+            .finish_non_exhaustive()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    fn secret(length: usize) -> Option<SecretPages> {
+        match SecretPages::try_new(length) {
+            Ok(secret) => Some(secret),
+            Err(err) if err.is_lock_limit_exceeded() => None,
+            Err(err) => panic!("{err}"),
+        }
+    }
+    #[test]
+    fn test_secret_pages() {
+        let Some(mut secret) = secret(1) else {
+            return;
+        };
+        assert_eq!(secret.len(), page_size());
+        assert!(secret.iter().all(|byte| *byte == 0));
+        secret[..3].copy_from_slice(&[123, 45, 67]);
+        assert_eq!(&secret[..3], &[123, 45, 67]);
+        if !secret.is_secret_memory() {
+            assert!(secret.pages.is_locked());
+        }
+        let debug = format!("{secret:?}");
+        assert!(debug.starts_with("SecretPages"));
+        assert!(!debug.contains("123"));
+        secret.wipe();
+        assert!(secret.iter().all(|byte| *byte == 0));
+    }
+    #[test]
+    fn test_secret_pages_zero() {
+        assert!(SecretPages::try_new(0).is_err());
+    }
+}
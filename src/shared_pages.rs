@@ -0,0 +1,169 @@
+use crate::*;
+use std::fs::File;
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use std::sync::Arc;
+#[cfg(target_os = "linux")]
+extern "C" {
+    fn memfd_create(name: *const std::ffi::c_char, flags: std::ffi::c_uint) -> c_int;
+    fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+}
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Allocates new [`Pages`] of size at least `length`, rounded up to next page boundary if necessary, backed by an anonymous
+    /// shared memory object(`memfd`). The object can be accessed using [`Self::shared_fd`], and mapped again, in this or
+    /// any other process it is sent to(e.g. by passing it over a unix socket), using [`Self::map_shared_fd`].
+    /// # Panics
+    /// Panics when a 0-sized allocation is attempted, or if kernel can't/refuses to allocate requested Pages.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let mut memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new_shared(0x1000);
+    /// memory[0] = 42;
+    /// // Map the same memory again, as read-only.
+    /// // Neither of the mappings is borrowed while the other one changes the memory.
+    /// let view:Pages<AllowRead,DenyWrite,DenyExec> =
+    ///     unsafe { Pages::map_shared_fd(memory.shared_fd().unwrap(), 0, 0x1000) }.unwrap();
+    /// assert_eq!(view[0], 42);
+    /// memory[0] = 43;
+    /// assert_eq!(view[0], 43);
+    /// ```
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn new_shared(length: usize) -> Self {
+        assert_ne!(length, 0, "0 - sized allcations are not allowed!");
+        Self::try_new_shared(length).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Fallible version of [`Self::new_shared`]. The object is sealed with `F_SEAL_SHRINK`, so it can never be shrunk, even
+    /// by other processes it is sent to.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if creating or mapping the shared memory object fails, including
+    /// 0-sized allocations.
+    #[cfg(target_os = "linux")]
+    pub fn try_new_shared(length: usize) -> Result<Self, PagesError> {
+        const MFD_CLOEXEC: std::ffi::c_uint = 0x1;
+        const MFD_ALLOW_SEALING: std::ffi::c_uint = 0x2;
+        const F_ADD_SEALS: c_int = 1033;
+        const F_SEAL_SHRINK: c_int = 0x2;
+        let len = next_page_boundary(length);
+        if len == 0 {
+            return Err(PagesError::invalid_argument(PagesOperation::Map));
+        }
+        let fd = unsafe { memfd_create(c"memory_pages".as_ptr(), MFD_CLOEXEC | MFD_ALLOW_SEALING) };
+        if fd == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Map));
+        }
+        let file = unsafe { <File as std::os::fd::FromRawFd>::from_raw_fd(fd) };
+        file.set_len(len as u64).map_err(|err| io_error(&err))?;
+        if unsafe { fcntl(fd, F_ADD_SEALS, F_SEAL_SHRINK) } == -1 {
+            return Err(PagesError::last_os_error(PagesOperation::Map));
+        }
+        // The object was just created, so it is only accessible through this mapping.
+        unsafe { Self::map_shared_file(file, 0, len) }
+    }
+    /// Maps `length` bytes of an existing shared memory object `fd`, starting at `offset`, with permissions described by
+    /// `R`, `W` and `E`. Changes made through any of the mappings of the object are visible in all of them. The returned
+    /// [`Pages`] keeps its own handle to the object, which can be accessed using [`Self::shared_fd`].
+    /// # Safety
+    /// The caller must ensure that, for as long as the mapping lives:
+    /// 1. The object is not shrunk(by this or any other process), since accessing mapped memory past its end raises SIGBUS.
+    /// 2. No references to the mapped memory, obtained from this or any other mapping of the same range(in this or any
+    ///    other process), are alive while the memory is changed through another mapping.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Map`] if `offset` is not a multiple of [`allocation_granularity`], if the
+    /// mapped range is empty or does not lie within the object, if `fd` does not allow the requested permissions, or if the
+    /// kernel can't/refuses to map it.
+    pub unsafe fn map_shared_fd(
+        fd: impl AsFd,
+        offset: u64,
+        length: usize,
+    ) -> Result<Self, PagesError> {
+        let file = File::from(
+            fd.as_fd()
+                .try_clone_to_owned()
+                .map_err(|err| io_error(&err))?,
+        );
+        Self::map_shared_file(file, offset, length)
+    }
+    /// # Safety
+    /// Same as [`Self::map_shared_fd`].
+    unsafe fn map_shared_file(file: File, offset: u64, length: usize) -> Result<Self, PagesError> {
+        let mut pages = Self::map_file(&file, offset, length, MapMode::Shared)?;
+        pages.fd = Some(Arc::new(OwnedFd::from(file)));
+        Ok(pages)
+    }
+    /// Returns the shared memory object backing this [`Pages`], or [`None`] if it is not backed by one.
+    /// # Safety
+    /// The object can be used to change the memory of this [`Pages`] behind its back(e.g. by writing to it, or mapping it
+    /// again), so the caller must ensure that the object:
+    /// 1. Is not shrunk. Objects created by [`Self::new_shared`] are sealed against it, but objects mapped using
+    ///    [`Self::map_shared_fd`] may not be.
+    /// 2. Is only mapped again in line with the safety rules of [`Self::map_shared_fd`], and not otherwise written to while
+    ///    memory of this [`Pages`] is borrowed.
+    ///
+    /// The same applies to any duplicate of it, including ones sent to other processes.
+    #[must_use]
+    pub unsafe fn shared_fd(&self) -> Option<BorrowedFd<'_>> {
+        self.fd.as_ref().map(|fd| fd.as_fd())
+    }
+}
+fn io_error(err: &std::io::Error) -> PagesError {
+    err.raw_os_error().map_or_else(
+        || PagesError::invalid_argument(PagesOperation::Map),
+        |code| PagesError::new(PagesOperation::Map, code),
+    )
+}
+#[cfg(all(test, target_os = "linux"))]
+mod test {
+    use super::*;
+    #[test]
+    fn test_shared_pages() {
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new_shared(1);
+        assert_eq!(pages.len(), page_size());
+        assert_eq!(pages.map_mode(), Some(MapMode::Shared));
+        pages[1] = 2;
+        let view: Pages<AllowRead, DenyWrite, DenyExec> =
+            unsafe { Pages::map_shared_fd(pages.shared_fd().unwrap(), 0, page_size()) }.unwrap();
+        assert!(unsafe { view.shared_fd() }.is_some());
+        // The object outlives the mapping it was created with.
+        drop(pages);
+        assert_eq!(view[1], 2);
+        let mut view = view.allow_write();
+        view[2] = 3;
+        assert_eq!(view[2], 3);
+    }
+    #[test]
+    fn test_shared_pages_out_of_range() {
+        let pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new_shared(page_size());
+        let fd = unsafe { pages.shared_fd() }.unwrap();
+        let map = |offset, len| unsafe {
+            Pages::<AllowRead, DenyWrite, DenyExec>::map_shared_fd(fd, offset, len)
+        };
+        assert!(map(0, 2 * page_size()).is_err());
+        assert!(map(1, 1).is_err());
+        assert!(Pages::<AllowRead, AllowWrite, DenyExec>::try_new_shared(0).is_err());
+    }
+    #[test]
+    fn test_shared_pages_sealed() {
+        let pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new_shared(page_size());
+        let file = File::from(
+            unsafe { pages.shared_fd() }
+                .unwrap()
+                .try_clone_to_owned()
+                .unwrap(),
+        );
+        // Shrinking would make accessing `pages` raise SIGBUS.
+        assert!(file.set_len(0).is_err());
+        assert!(file.set_len(2 * page_size() as u64).is_ok());
+        assert_eq!(pages[page_size() - 1], 0);
+    }
+    #[test]
+    fn test_shared_pages_resize() {
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new_shared(page_size());
+        // The object is not grown together with the mapping, so growing is refused.
+        let err = pages.try_resize(4 * page_size()).unwrap_err();
+        assert_eq!(err.operation(), PagesOperation::Remap);
+        let last = pages.len() - 1;
+        assert_eq!(last, page_size() - 1);
+        pages[last] = 1;
+        assert_eq!(pages[last], 1);
+    }
+}
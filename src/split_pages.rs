@@ -0,0 +1,180 @@
+use crate::*;
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Splits `self` at byte offset `at` into two independently owned [`Pages`]: the first one covering `[0, at)`, and the
+    /// second one covering `[at, len)`. Each of them can change its permissions on its own, and releases only its own range
+    /// when dropped. Both of them keep the huge page size, lock, file and shared memory object of `self`.
+    /// # Panics
+    /// Panics if `self` can't be split at `at`. See [`Self::try_split_at_page`] for when this happens.
+    /// # Examples
+    /// Turning a single allocation into a read-only code section and a writable data section.
+    /// ```
+    /// # use memory_pages::*;
+    /// let image:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(3 * page_size());
+    /// let (code, mut data) = image.split_at_page(page_size());
+    /// // With `allow_exec` enabled, this could be `set_protected_exec` instead.
+    /// let code = code.deny_write();
+    /// data[0] = 1;
+    /// assert_eq!(code.len(),page_size());
+    /// assert_eq!(data.len(),2 * page_size());
+    /// ```
+    #[must_use]
+    pub fn split_at_page(self, at: usize) -> (Self, Self) {
+        self.try_split_at_page(at)
+            .unwrap_or_else(|(_, err)| panic!("Can't split pages at {at}: {err}"))
+    }
+    /// Fallible version of [`Self::split_at_page`]. On failure, `self` is given back unchanged.
+    /// # Errors
+    /// Returns an error with [`PagesOperation::Split`] if `at` is 0, is not less than `len`, or is not a multiple of the
+    /// size of the pages backing `self`([`page_size`], or the huge page size). Pages surrounded by guard regions can't be
+    /// split, and neither can any pages on windows, which can only release whole allocations.
+    /// # Examples
+    /// ```
+    /// # use memory_pages::*;
+    /// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(2 * page_size());
+    /// let (memory, err) = memory.try_split_at_page(1).unwrap_err();
+    /// assert_eq!(err.operation(),PagesOperation::Split);
+    /// assert_eq!(memory.len(),2 * page_size());
+    /// ```
+    pub fn try_split_at_page(mut self, at: usize) -> Result<(Self, Self), (Self, PagesError)> {
+        let splittable = cfg!(target_family = "unix")
+            && self.guard == 0
+            && at != 0
+            && at < self.len
+            && at.is_multiple_of(self.granularity());
+        if !splittable {
+            return Err((self, PagesError::invalid_argument(PagesOperation::Split)));
+        }
+        let mut tail = Self::from_raw_parts(self.ptr.wrapping_add(at), self.len - at);
+        tail.huge = self.huge;
+        tail.file = self.file;
+        tail.locked = self.locked;
+        #[cfg(target_family = "unix")]
+        {
+            tail.fd = self.fd.clone();
+        }
+        self.set_len(at);
+        Ok((self, tail))
+    }
+    /// Joins `a` and `b`, which must be virtually adjacent(in either order), into one [`Pages`] covering both of them. This is
+    /// the counterpart of [`Self::split_at_page`]: the permissions of both [`Pages`] are already the same, so no syscalls are
+    /// needed. Returns both inputs unchanged if they can't be joined.
+    /// # Errors
+    /// Gives back `(a, b)` if they are not contiguous, if either of them is surrounded by guard regions, if they differ in
+    /// huge page size, being locked, file mapping mode or shared memory object, or on windows, which can only release whole
+    /// allocations.
+    /// # Examples
+    /// Building an image section by section, and handing it out as one region.
+    /// ```
+    /// # use memory_pages::*;
+    /// let image:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(2 * page_size());
+    /// let (mut header, mut body) = image.split_at_page(page_size());
+    /// header[0] = 1;
+    /// body[0] = 2;
+    /// let image = Pages::try_join(header, body).unwrap();
+    /// assert_eq!(image.len(),2 * page_size());
+    /// assert_eq!(image[page_size()],2);
+    /// ```
+    pub fn try_join(a: Self, b: Self) -> Result<Self, (Self, Self)> {
+        let swapped = b.ptr.wrapping_add(b.len) == a.ptr;
+        let (mut head, tail) = if swapped { (b, a) } else { (a, b) };
+        #[cfg(target_family = "unix")]
+        let same_fd = match (&head.fd, &tail.fd) {
+            (Some(head_fd), Some(tail_fd)) => std::sync::Arc::ptr_eq(head_fd, tail_fd),
+            (head_fd, tail_fd) => head_fd.is_none() && tail_fd.is_none(),
+        };
+        #[cfg(target_family = "windows")]
+        let same_fd = false;
+        let joinable = same_fd
+            && head.ptr.wrapping_add(head.len) == tail.ptr
+            && head.guard == 0
+            && tail.guard == 0
+            && head.huge == tail.huge
+            && head.file == tail.file
+            && head.locked == tail.locked;
+        if !joinable {
+            return Err(if swapped { (tail, head) } else { (head, tail) });
+        }
+        let (_, tail_len) = tail.into_raw_parts();
+        head.set_len(head.len + tail_len);
+        Ok(head)
+    }
+}
+#[cfg(all(test, target_family = "unix"))]
+mod test {
+    use super::*;
+    #[test]
+    fn test_split_prot() {
+        let page_size = page_size();
+        let mut pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(4 * page_size);
+        pages[page_size] = 7;
+        let (head, mut tail) = pages.split_at_page(page_size);
+        assert_eq!(head.len(), page_size);
+        assert_eq!(tail.len(), 3 * page_size);
+        assert_eq!(tail[0], 7);
+        let head = head.deny_write();
+        tail[1] = 8;
+        // Dropping one half leaves the other one mapped.
+        drop(head);
+        assert_eq!(tail[1], 8);
+        let (tail, mut end) = tail.split_at_page(2 * page_size);
+        end[page_size - 1] = 9;
+        drop(tail);
+        assert_eq!(end[page_size - 1], 9);
+    }
+    #[test]
+    fn test_split_invalid() {
+        let page_size = page_size();
+        let pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(2 * page_size);
+        let (pages, _) = pages.try_split_at_page(0).unwrap_err();
+        let (pages, _) = pages.try_split_at_page(page_size + 1).unwrap_err();
+        let (pages, err) = pages.try_split_at_page(2 * page_size).unwrap_err();
+        assert_eq!(err.operation(), PagesOperation::Split);
+        assert_eq!(pages.len(), 2 * page_size);
+        let guarded: Pages<AllowRead, AllowWrite, DenyExec> =
+            Pages::new_guarded(2 * page_size, page_size);
+        assert!(guarded.try_split_at_page(page_size).is_err());
+    }
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_split_shared() {
+        let page_size = page_size();
+        let pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new_shared(2 * page_size);
+        let (head, mut tail) = pages.split_at_page(page_size);
+        drop(head);
+        tail[0] = 3;
+        let view: Pages<AllowRead, DenyWrite, DenyExec> =
+            unsafe { Pages::map_shared_fd(tail.shared_fd().unwrap(), page_size as u64, page_size) }
+                .unwrap();
+        assert_eq!(view[0], 3);
+    }
+    #[test]
+    fn test_join() {
+        let page_size = page_size();
+        let pages: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(3 * page_size);
+        let (head, mut tail) = pages.split_at_page(page_size);
+        tail[0] = 5;
+        // Order of the arguments does not matter.
+        let mut pages = Pages::try_join(tail, head).unwrap();
+        assert_eq!(pages.len(), 3 * page_size);
+        assert_eq!(pages[page_size], 5);
+        pages[3 * page_size - 1] = 6;
+        let (head, tail) = pages.split_at_page(page_size);
+        let (middle, end) = tail.split_at_page(page_size);
+        // Not contiguous: both inputs are given back, in the same order.
+        let (head, end) = Pages::try_join(head, end).unwrap_err();
+        assert_eq!(head.len(), page_size);
+        assert_eq!(end[page_size - 1], 6);
+        let tail = Pages::try_join(middle, end).unwrap();
+        let pages = Pages::try_join(head, tail).unwrap();
+        assert_eq!(pages[3 * page_size - 1], 6);
+    }
+    #[test]
+    fn test_join_unrelated() {
+        let a: Pages<AllowRead, AllowWrite, DenyExec> = Pages::new(page_size());
+        let b: Pages<AllowRead, AllowWrite, DenyExec> =
+            Pages::new_guarded(page_size(), page_size());
+        let (a, b) = Pages::try_join(a, b).unwrap_err();
+        assert_eq!(b.guard_size(), page_size());
+        drop((a, b));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::*;
+/// Number of distinct combinations of read, write and execute permissions.
+const PROTECTIONS: usize = 8;
+/// Index of the counters summing up all permission combinations.
+const TOTAL: usize = PROTECTIONS;
+struct Counters {
+    bytes: AtomicUsize,
+    mappings: AtomicUsize,
+    peak_bytes: AtomicUsize,
+    peak_mappings: AtomicUsize,
+}
+impl Counters {
+    const fn new() -> Self {
+        Self {
+            bytes: AtomicUsize::new(0),
+            mappings: AtomicUsize::new(0),
+            peak_bytes: AtomicUsize::new(0),
+            peak_mappings: AtomicUsize::new(0),
+        }
+    }
+    fn add(&self, bytes: usize, mappings: usize) {
+        let current = self.bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.peak_bytes.fetch_max(current, Ordering::Relaxed);
+        let current = self.mappings.fetch_add(mappings, Ordering::Relaxed) + mappings;
+        self.peak_mappings.fetch_max(current, Ordering::Relaxed);
+    }
+    fn sub(&self, bytes: usize, mappings: usize) {
+        self.bytes.fetch_sub(bytes, Ordering::Relaxed);
+        self.mappings.fetch_sub(mappings, Ordering::Relaxed);
+    }
+    fn load(&self) -> MappingStats {
+        MappingStats {
+            bytes: self.bytes.load(Ordering::Relaxed),
+            mappings: self.mappings.load(Ordering::Relaxed),
+            peak_bytes: self.peak_bytes.load(Ordering::Relaxed),
+            peak_mappings: self.peak_mappings.load(Ordering::Relaxed),
+        }
+    }
+}
+static COUNTERS: [Counters; PROTECTIONS + 1] = [const { Counters::new() }; PROTECTIONS + 1];
+pub(crate) fn record_map(protection: usize, bytes: usize) {
+    COUNTERS[protection].add(bytes, 1);
+    COUNTERS[TOTAL].add(bytes, 1);
+}
+pub(crate) fn record_unmap(protection: usize, bytes: usize) {
+    COUNTERS[protection].sub(bytes, 1);
+    COUNTERS[TOTAL].sub(bytes, 1);
+}
+pub(crate) fn record_resize(protection: usize, old_bytes: usize, new_bytes: usize) {
+    for counters in [&COUNTERS[protection], &COUNTERS[TOTAL]] {
+        if new_bytes > old_bytes {
+            counters.add(new_bytes - old_bytes, 0);
+        } else {
+            counters.sub(old_bytes - new_bytes, 0);
+        }
+    }
+}
+pub(crate) fn record_cast(from: usize, to: usize, bytes: usize) {
+    if from != to {
+        COUNTERS[from].sub(bytes, 1);
+        COUNTERS[to].add(bytes, 1);
+    }
+}
+/// Name of permission combination `protection`, like `"RW-"` or `"R-X"`.
+pub(crate) fn protection_name(protection: usize) -> &'static str {
+    const NAMES: [&str; PROTECTIONS] = ["---", "R--", "-W-", "RW-", "--X", "R-X", "-WX", "RWX"];
+    NAMES[protection]
+}
+impl<R: ReadPremisionMarker, W: WritePremisionMarker, E: ExecPremisionMarker> Pages<R, W, E> {
+    /// Index of the counters of permission combination of this [`Pages`].
+    pub(crate) fn protection_index() -> usize {
+        usize::from(R::allow_read())
+            | usize::from(W::allow_write()) << 1
+            | usize::from(E::allow_exec()) << 2
+    }
+}
+/// Returns a snapshot of statistics of all [`Pages`] mapped by this process, both in total and broken down by permissions.
+/// Only the usable memory of [`Pages`] is counted: guard regions surrounding it are not. Counters are updated without
+/// locking, so a snapshot taken while other threads map or unmap pages may be slightly inconsistent.
+/// # Examples
+/// ```
+/// # use memory_pages::*;
+/// let memory:Pages<AllowRead,AllowWrite,DenyExec> = Pages::new(0x4000);
+/// let stats = stats();
+/// assert!(stats.total().bytes() >= 0x4000);
+/// assert!(stats.get(true, true, false).mappings() >= 1);
+/// for (protection, stats) in stats.iter(){
+///     println!("{protection}: {} bytes in {} mappings", stats.bytes(), stats.mappings());
+/// }
+/// ```
+#[must_use]
+pub fn stats() -> PagesStats {
+    PagesStats {
+        by_protection: std::array::from_fn(|protection| COUNTERS[protection].load()),
+        total: COUNTERS[TOTAL].load(),
+    }
+}
+/// Statistics of [`Pages`] with a certain combination of permissions, or of all of them. Returned by [`stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MappingStats {
+    bytes: usize,
+    mappings: usize,
+    peak_bytes: usize,
+    peak_mappings: usize,
+}
+impl MappingStats {
+    /// Returns the number of bytes currently mapped.
+    #[must_use]
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+    /// Returns the number of [`Pages`] currently alive.
+    #[must_use]
+    pub fn mappings(&self) -> usize {
+        self.mappings
+    }
+    /// Returns the largest number of bytes mapped at once.
+    #[must_use]
+    pub fn peak_bytes(&self) -> usize {
+        self.peak_bytes
+    }
+    /// Returns the largest number of [`Pages`] alive at once.
+    #[must_use]
+    pub fn peak_mappings(&self) -> usize {
+        self.peak_mappings
+    }
+}
+/// A snapshot of statistics of all [`Pages`] mapped by this process, returned by [`stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PagesStats {
+    by_protection: [MappingStats; PROTECTIONS],
+    total: MappingStats,
+}
+impl PagesStats {
+    /// Returns statistics of all [`Pages`], regardless of their permissions.
+    #[must_use]
+    pub fn total(&self) -> MappingStats {
+        self.total
+    }
+    /// Returns statistics of [`Pages`] with exactly the given permissions.
+    #[must_use]
+    pub fn get(&self, read: bool, write: bool, exec: bool) -> MappingStats {
+        self.by_protection[usize::from(read) | usize::from(write) << 1 | usize::from(exec) << 2]
+    }
+    /// Iterates over statistics of each combination of permissions, named like `"RW-"` or `"R-X"`.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, MappingStats)> + '_ {
+        (0..PROTECTIONS)
+            .map(|protection| (protection_name(protection), self.by_protection[protection]))
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    const LEN: usize = 0x40_0000;
+    /// Counters are shared by all tests running in parallel, so only write-only mappings, which no other test makes, can
+    /// be checked exactly.
+    fn write_only() -> MappingStats {
+        stats().get(false, true, false)
+    }
+    #[test]
+    fn test_stats() {
+        let before = write_only();
+        let pages: Pages<DenyRead, AllowWrite, DenyExec> = Pages::new(LEN);
+        let mapped = write_only();
+        assert_eq!(mapped.bytes() - before.bytes(), LEN);
+        assert_eq!(mapped.mappings() - before.mappings(), 1);
+        assert!(mapped.peak_bytes() >= mapped.bytes());
+        let total = stats().total();
+        assert!(total.bytes() >= LEN);
+        assert!(total.peak_mappings() >= total.mappings());
+        // Changing permissions moves the mapping to the counters of its new permissions.
+        let pages = pages.allow_read();
+        assert_eq!(write_only().bytes(), before.bytes());
+        let pages = pages.deny_read();
+        assert_eq!(write_only().bytes() - before.bytes(), LEN);
+        drop(pages);
+        let dropped = write_only();
+        assert_eq!(dropped.bytes(), before.bytes());
+        assert_eq!(dropped.mappings(), before.mappings());
+        assert!(dropped.peak_bytes() - before.bytes() >= LEN);
+        assert_eq!(stats().iter().count(), 8);
+        assert_eq!(stats().iter().nth(5).unwrap().0, "R-X");
+    }
+}